@@ -0,0 +1,99 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fixed_torus::slice::{
+    add_assign_slice, dot_product_binary, dot_product_int, scalar_mul_add, sub_assign_slice,
+};
+use fixed_torus::Torus;
+
+const LENGTHS: [usize; 3] = [500, 1024, 2048];
+
+fn torus(len: usize, seed: u32) -> Vec<Torus> {
+    (0..len as u32)
+        .map(|i| Torus::new((i ^ seed).wrapping_mul(0x9e37_79b9)))
+        .collect()
+}
+
+fn bench_add_sub(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add_sub_assign");
+    for len in LENGTHS {
+        let src = torus(len, 1);
+        let mut dst = torus(len, 2);
+        group.bench_with_input(BenchmarkId::new("kernel", len), &len, |b, _| {
+            b.iter(|| {
+                add_assign_slice(&mut dst, black_box(&src));
+                sub_assign_slice(&mut dst, black_box(&src));
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("naive", len), &len, |b, _| {
+            b.iter(|| {
+                for (d, &s) in dst.iter_mut().zip(black_box(&src)) {
+                    *d += s;
+                }
+                for (d, &s) in dst.iter_mut().zip(black_box(&src)) {
+                    *d -= s;
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_scalar_mul_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scalar_mul_add");
+    for len in LENGTHS {
+        let src = torus(len, 3);
+        let mut dst = torus(len, 4);
+        group.bench_with_input(BenchmarkId::new("kernel", len), &len, |b, _| {
+            b.iter(|| scalar_mul_add(&mut dst, black_box(&src), black_box(-3)))
+        });
+        group.bench_with_input(BenchmarkId::new("naive", len), &len, |b, _| {
+            b.iter(|| {
+                let k = black_box(-3);
+                for (d, &s) in dst.iter_mut().zip(black_box(&src)) {
+                    *d += s * k;
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_dot_product(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dot_product");
+    for len in LENGTHS {
+        let a = torus(len, 5);
+        let ints: Vec<i32> = (0..len).map(|i| (i % 3) as i32 - 1).collect();
+        let bools: Vec<bool> = (0..len).map(|i| i % 3 == 0).collect();
+        group.bench_with_input(BenchmarkId::new("int_kernel", len), &len, |b, _| {
+            b.iter(|| dot_product_int(black_box(&a), black_box(&ints)))
+        });
+        group.bench_with_input(BenchmarkId::new("int_naive", len), &len, |b, _| {
+            b.iter(|| {
+                black_box(&a)
+                    .iter()
+                    .zip(black_box(&ints))
+                    .fold(Torus::new(0), |acc, (&x, &y)| acc + x * y)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("binary_kernel", len), &len, |b, _| {
+            b.iter(|| dot_product_binary(black_box(&a), black_box(&bools)))
+        });
+        group.bench_with_input(BenchmarkId::new("binary_naive", len), &len, |b, _| {
+            b.iter(|| {
+                black_box(&a)
+                    .iter()
+                    .zip(black_box(&bools))
+                    .filter(|(_, &y)| y)
+                    .fold(Torus::new(0), |acc, (&x, _)| acc + x)
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_add_sub,
+    bench_scalar_mul_add,
+    bench_dot_product
+);
+criterion_main!(benches);
@@ -0,0 +1,353 @@
+//! Programmable bootstrapping.
+//!
+//! The phase of an LWE sample is switched to Z_2N, the test vector is rotated
+//! by X^-phase with a chain of CMux over the bootstrapping key, and the
+//! constant coefficient is extracted back to an LWE sample under the key
+//! [`TrlweSecretKey::to_lwe_key`].
+
+#[cfg(feature = "random")]
+use crate::decomposition::GadgetDecomposer;
+use crate::lwe::LweCiphertext;
+#[cfg(feature = "random")]
+use crate::lwe::LweSecretKey;
+#[cfg(feature = "random")]
+use crate::polynomial::IntPolynomial;
+use crate::polynomial::{PolynomialMultiplier, TorusPolynomial};
+use crate::trgsw::TrgswCiphertext;
+use crate::trlwe::TrlweCiphertext;
+#[cfg(feature = "random")]
+use crate::trlwe::TrlweSecretKey;
+use crate::wire::{checked_decomposer, Kind, WireError, WireFormat, WireReader, WireWriter};
+use crate::{Torus, TorusRepr};
+#[cfg(feature = "random")]
+use num_traits::identities::ConstZero;
+use std::io::{Read, Write};
+
+/// TRGSW encryptions of every bit of an LWE secret key
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "BootstrappingKeyRaw<N>"))]
+pub struct BootstrappingKey<const N: usize> {
+    keys: Vec<TrgswCiphertext<N>>,
+}
+
+/// Unchecked fields of a deserialized [`BootstrappingKey`]
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct BootstrappingKeyRaw<const N: usize> {
+    keys: Vec<TrgswCiphertext<N>>,
+}
+
+#[cfg(feature = "serde")]
+impl<const N: usize> TryFrom<BootstrappingKeyRaw<N>> for BootstrappingKey<N> {
+    type Error = &'static str;
+
+    fn try_from(raw: BootstrappingKeyRaw<N>) -> Result<Self, Self::Error> {
+        // the wire format stores k and the decomposer once for all keys
+        if let Some(first) = raw.keys.first() {
+            if raw
+                .keys
+                .iter()
+                .any(|key| key.k() != first.k() || key.decomposer() != first.decomposer())
+            {
+                return Err("keys with different parameters");
+            }
+        }
+        Ok(Self { keys: raw.keys })
+    }
+}
+
+/// tv[j] = lut_fn(j / 2N) for j < N
+pub fn test_vector<const N: usize>(lut_fn: impl Fn(Torus) -> Torus) -> TorusPolynomial<N> {
+    let log = (2 * N).trailing_zeros();
+    TorusPolynomial::new(std::array::from_fn(|j| {
+        lut_fn(Torus::new((j as TorusRepr) << (TorusRepr::BITS - log)))
+    }))
+}
+
+impl<const N: usize> BootstrappingKey<N> {
+    #[cfg(feature = "random")]
+    pub fn generate(
+        lwe_key: &LweSecretKey,
+        trlwe_key: &TrlweSecretKey<N>,
+        decomposer: GadgetDecomposer,
+        noise_std: f64,
+        mul: &impl PolynomialMultiplier<N>,
+        state: &mut impl rand::Rng,
+    ) -> Self {
+        Self::from_key_bits(lwe_key, |mu| {
+            TrgswCiphertext::encrypt(trlwe_key, mu, decomposer, noise_std, mul, state)
+        })
+    }
+
+    /// [`Self::generate`] with the masks and the noise drawn from separate
+    /// generators, see [`TrgswCiphertext::encrypt_with_streams`]
+    #[cfg(feature = "random")]
+    pub fn generate_with_streams(
+        lwe_key: &LweSecretKey,
+        trlwe_key: &TrlweSecretKey<N>,
+        decomposer: GadgetDecomposer,
+        noise_std: f64,
+        mul: &impl PolynomialMultiplier<N>,
+        mask_state: &mut impl rand::Rng,
+        noise_state: &mut impl rand::Rng,
+    ) -> Self {
+        Self::from_key_bits(lwe_key, |mu| {
+            TrgswCiphertext::encrypt_with_streams(
+                trlwe_key,
+                mu,
+                decomposer,
+                noise_std,
+                mul,
+                mask_state,
+                noise_state,
+            )
+        })
+    }
+
+    /// encrypt(s) for every bit s of lwe_key, as a constant polynomial
+    #[cfg(feature = "random")]
+    fn from_key_bits(
+        lwe_key: &LweSecretKey,
+        mut encrypt: impl FnMut(&IntPolynomial<N>) -> TrgswCiphertext<N>,
+    ) -> Self {
+        // blind rotation selects with CMux, which only works for bits
+        assert!(
+            lwe_key.coefs().iter().all(|&s| s == 0 || s == 1),
+            "bootstrapping needs a binary LWE key"
+        );
+        let keys = lwe_key
+            .coefs()
+            .iter()
+            .map(|&s| {
+                let mut mu = IntPolynomial::ZERO;
+                mu.coefs[0] = s;
+                encrypt(&mu)
+            })
+            .collect();
+        Self { keys }
+    }
+
+    /// Dimension of the LWE samples it can bootstrap
+    pub fn dimension(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// TRLWE encryption of X^-round(2N * phase(ct)) * tv
+    pub fn blind_rotate(
+        &self,
+        ct: &LweCiphertext,
+        tv: &TorusPolynomial<N>,
+        mul: &impl PolynomialMultiplier<N>,
+    ) -> TrlweCiphertext<N> {
+        assert_eq!(ct.dimension(), self.dimension(), "dimension mismatch");
+        assert!(N.is_power_of_two(), "N must be a power of two");
+
+        // round(t * 2N), in [0, 2N)
+        let log_2n = (2 * N).trailing_zeros();
+        let k = self.keys.first().map_or(1, |key| key.k());
+        let body = ct.body.mod_switch(log_2n) as usize;
+        let mut acc = TrlweCiphertext::trivial(tv.mul_by_monomial(2 * N - body), k);
+
+        let mask = Torus::mod_switch_slice(&ct.mask, log_2n);
+        for (key, &a) in self.keys.iter().zip(&mask) {
+            if a == 0 {
+                continue;
+            }
+            acc = key.cmux(&acc.mul_by_monomial(a as usize), &acc, mul);
+        }
+        acc
+    }
+
+    /// LWE encryption of lut_fn(φ) if φ = phase(ct) is in [0, 1/2), and of
+    /// -lut_fn(φ - 1/2) otherwise, with φ rounded to a multiple of 1/2N.
+    /// The result is under the extracted key of dimension kN.
+    pub fn bootstrap(
+        &self,
+        ct: &LweCiphertext,
+        lut_fn: impl Fn(Torus) -> Torus,
+        mul: &impl PolynomialMultiplier<N>,
+    ) -> LweCiphertext {
+        let tv = test_vector::<N>(lut_fn);
+        self.blind_rotate(ct, &tv, mul).sample_extract(0)
+    }
+}
+
+impl<const N: usize> WireFormat for BootstrappingKey<N> {
+    fn write_to(&self, writer: impl Write) -> Result<(), WireError> {
+        let mut w = WireWriter::new(writer, Kind::BootstrappingKey)?;
+        w.u32(N as u32)?;
+        match self.keys.first() {
+            Some(key) => {
+                w.u32(key.k() as u32)?;
+                w.decomposer(key.decomposer())?;
+            }
+            None => {
+                w.u32(0)?;
+                w.u32(0)?;
+                w.u32(0)?;
+            }
+        }
+        w.u64(self.keys.len() as u64)?;
+        for key in &self.keys {
+            key.write_payload(&mut w)?;
+        }
+        Ok(())
+    }
+
+    fn read_from(reader: impl Read) -> Result<Self, WireError> {
+        let mut r = WireReader::new(reader, Kind::BootstrappingKey)?;
+        r.degree::<N>()?;
+        let k = r.count()?;
+        let base_log = r.u32()?;
+        let level = r.u32()?;
+        let dimension = r.len()?;
+        if dimension == 0 {
+            return Ok(Self { keys: Vec::new() });
+        }
+        let decomposer = checked_decomposer(base_log, level)?;
+        let mut keys = Vec::new();
+        for _ in 0..dimension {
+            keys.push(TrgswCiphertext::read_payload(&mut r, k, decomposer)?);
+        }
+        Ok(Self { keys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::TorusRng;
+
+    #[test]
+    fn test_test_vector() {
+        let tv = test_vector::<4>(|t| t + t);
+        let expected = [0, 1 << 30, 1 << 31, 3 << 30].map(Torus::new);
+        assert_eq!(tv.coefs, expected);
+    }
+
+    #[cfg(feature = "random")]
+    mod end_to_end {
+        use super::*;
+        use crate::fft::FftPlan;
+
+        const N: usize = 512;
+        const LWE_DIMENSION: usize = 128;
+        const LWE_STD: f64 = 1e-5;
+        const TRGSW_STD: f64 = 1e-9;
+        /// message space, one padding bit
+        const P: u32 = 4;
+
+        /// m / 2p shifted by half a slot, so noise stays inside the slot of m
+        fn encode(m: u32) -> Torus {
+            Torus::new(((2 * m + 1) as TorusRepr) << (TorusRepr::BITS - 2 - P.trailing_zeros()))
+        }
+
+        fn decode(t: Torus) -> u32 {
+            let slot = TorusRepr::BITS - 1 - P.trailing_zeros();
+            (t.inner() >> slot) % (2 * P)
+        }
+
+        #[test]
+        fn test_bootstrap_lut() {
+            let mut rng = TorusRng::from_seed([1; 32]);
+            let mul = FftPlan::<N>::new();
+            let lwe_key = LweSecretKey::generate(LWE_DIMENSION, &mut rng);
+            let trlwe_key = TrlweSecretKey::<N>::generate(1, &mut rng);
+            let extracted_key = trlwe_key.to_lwe_key();
+            let bk = BootstrappingKey::generate(
+                &lwe_key,
+                &trlwe_key,
+                GadgetDecomposer::new(7, 3),
+                TRGSW_STD,
+                &mul,
+                &mut rng,
+            );
+            assert_eq!(bk.dimension(), LWE_DIMENSION);
+
+            let f = |m: u32| (m * m + 1) % P;
+            for m in 0..P {
+                let ct = lwe_key.encrypt(encode(m), LWE_STD, &mut rng);
+                let out = bk.bootstrap(&ct, |t| encode(f(decode(t))), &mul);
+                assert_eq!(out.dimension(), N);
+                assert_eq!(decode(extracted_key.decrypt_phase(&out)), f(m), "m = {}", m);
+            }
+        }
+
+        #[test]
+        fn test_bootstrap_sign() {
+            let mut rng = TorusRng::from_seed([2; 32]);
+            let mul = FftPlan::<N>::new();
+            let lwe_key = LweSecretKey::generate(LWE_DIMENSION, &mut rng);
+            let trlwe_key = TrlweSecretKey::<N>::generate(1, &mut rng);
+            let extracted_key = trlwe_key.to_lwe_key();
+            let bk = BootstrappingKey::generate(
+                &lwe_key,
+                &trlwe_key,
+                GadgetDecomposer::new(7, 3),
+                TRGSW_STD,
+                &mul,
+                &mut rng,
+            );
+
+            let eighth = Torus::new(1 << 29);
+            for (phase, expected) in [(0.125, 1), (0.375, 1), (0.625, -1), (0.875, -1)] {
+                let ct = lwe_key.encrypt(Torus::from(phase), LWE_STD, &mut rng);
+                let out = bk.bootstrap(&ct, |_| eighth, &mul);
+                assert_eq!(extracted_key.decrypt_phase(&out).sign(), expected);
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "bootstrapping needs a binary LWE key")]
+        fn test_non_binary_key() {
+            use crate::keygen::TernaryKey;
+
+            let mut rng = TorusRng::from_seed([3; 32]);
+            let mul = FftPlan::<N>::new();
+            let lwe_key = LweSecretKey::generate_with(
+                &TernaryKey { hamming_weight: 8 },
+                LWE_DIMENSION,
+                &mut rng,
+            );
+            let trlwe_key = TrlweSecretKey::<N>::generate(1, &mut rng);
+            BootstrappingKey::generate(
+                &lwe_key,
+                &trlwe_key,
+                GadgetDecomposer::new(7, 3),
+                TRGSW_STD,
+                &mul,
+                &mut rng,
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_invalid() {
+        use crate::decomposition::GadgetDecomposer;
+        use crate::polynomial::IntPolynomial;
+        use num_traits::identities::ConstZero;
+        use serde_json::{from_value, json, to_value};
+
+        // every key encrypted under the same parameters
+        let trgsw = |k, base_log| {
+            let decomposer = GadgetDecomposer::new(base_log, 2);
+            to_value(TrgswCiphertext::<8>::trivial(
+                &IntPolynomial::ZERO,
+                k,
+                decomposer,
+            ))
+            .unwrap()
+        };
+        let valid = json!({"keys": [trgsw(1, 4), trgsw(1, 4)]});
+        let bk = from_value::<BootstrappingKey<8>>(valid).unwrap();
+        assert_eq!(bk.dimension(), 2);
+        assert!(
+            from_value::<BootstrappingKey<8>>(json!({"keys": [trgsw(1, 4), trgsw(2, 4)]})).is_err()
+        );
+        assert!(
+            from_value::<BootstrappingKey<8>>(json!({"keys": [trgsw(1, 4), trgsw(1, 5)]})).is_err()
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::polynomial::{IntPolynomial, TorusPolynomial};
+use crate::{Torus, TorusRepr};
+use num_traits::identities::ConstZero;
+
+/// Gadget decomposition in base Bg = 2^base_log with `level` digits.
+///
+/// t is first rounded to its closest multiple of Bg^-level, then written as
+/// sum_j d_j * Bg^-(j+1) with balanced digits d_j in [-Bg/2, Bg/2).
+/// So the recomposition differs from t by at most 2^-(base_log * level + 1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GadgetDecomposerRaw"))]
+pub struct GadgetDecomposer {
+    pub base_log: u32,
+    pub level: usize,
+}
+
+/// Unchecked fields of a deserialized [`GadgetDecomposer`]
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GadgetDecomposerRaw {
+    base_log: u32,
+    level: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GadgetDecomposerRaw> for GadgetDecomposer {
+    type Error = &'static str;
+
+    fn try_from(raw: GadgetDecomposerRaw) -> Result<Self, Self::Error> {
+        Self::check(raw.base_log, raw.level)?;
+        Ok(Self::new(raw.base_log, raw.level))
+    }
+}
+
+impl GadgetDecomposer {
+    pub fn new(base_log: u32, level: usize) -> Self {
+        if let Err(e) = Self::check(base_log, level) {
+            panic!("{}", e);
+        }
+        Self { base_log, level }
+    }
+
+    /// Why new(base_log, level) would panic, if it does
+    pub(crate) fn check(base_log: u32, level: usize) -> Result<(), &'static str> {
+        if base_log == 0 || level == 0 {
+            return Err("invalid decomposition");
+        }
+        if base_log as u64 * level as u64 > TorusRepr::BITS as u64 {
+            return Err("decomposition exceeds torus precision");
+        }
+        Ok(())
+    }
+
+    /// Bg^-(j+1) on the torus
+    pub fn gadget(&self, j: usize) -> Torus {
+        Torus::new(1 << (TorusRepr::BITS - self.base_log * (j as u32 + 1)))
+    }
+
+    /// Upper bound of |recompose(decompose(t)) - t|, in units of 2^-32
+    pub fn max_error(&self) -> TorusRepr {
+        let shift = TorusRepr::BITS - self.base_log * self.level as u32;
+        if shift == 0 {
+            0
+        } else {
+            1 << (shift - 1)
+        }
+    }
+
+    /// Call f(j, d_j) for every digit, least significant first
+    fn for_each_digit(&self, t: Torus, mut f: impl FnMut(usize, i32)) {
+        let total = self.base_log * self.level as u32;
+        let shift = TorusRepr::BITS - total;
+        let base = 1i64 << self.base_log;
+
+        let mut rest = if shift == 0 {
+            t.inner() as i64
+        } else {
+            ((t.inner() as i64 + (1 << (shift - 1))) >> shift) & ((1 << total) - 1)
+        };
+
+        for j in (0..self.level).rev() {
+            let mut digit = rest & (base - 1);
+            rest >>= self.base_log;
+            if digit >= base / 2 {
+                digit -= base;
+                rest += 1;
+            }
+            f(j, digit as i32);
+        }
+    }
+
+    /// Digits of t, most significant first
+    pub fn decompose(&self, t: Torus) -> Vec<i32> {
+        let mut out = vec![0; self.level];
+        self.for_each_digit(t, |j, d| out[j] = d);
+        out
+    }
+
+    /// out[j][i] is the j-th digit of ts[i]
+    pub fn decompose_slice(&self, ts: &[Torus]) -> Vec<Vec<i32>> {
+        let mut out = vec![vec![0; ts.len()]; self.level];
+        for (i, &t) in ts.iter().enumerate() {
+            self.for_each_digit(t, |j, d| out[j][i] = d);
+        }
+        out
+    }
+
+    /// out[j] holds the j-th digits of every coefficient of p
+    pub fn decompose_polynomial<const N: usize>(
+        &self,
+        p: &TorusPolynomial<N>,
+    ) -> Vec<IntPolynomial<N>> {
+        let mut out = vec![IntPolynomial::<N>::ZERO; self.level];
+        for (i, &t) in p.coefs.iter().enumerate() {
+            self.for_each_digit(t, |j, d| out[j].coefs[i] = d);
+        }
+        out
+    }
+
+    /// sum_j d_j * Bg^-(j+1)
+    pub fn recompose(&self, digits: &[i32]) -> Torus {
+        assert_eq!(digits.len(), self.level, "level mismatch");
+        digits
+            .iter()
+            .enumerate()
+            .fold(Torus::ZERO, |acc, (j, &d)| acc + self.gadget(j) * d)
+    }
+
+    pub fn recompose_slice(&self, digits: &[Vec<i32>]) -> Vec<Torus> {
+        assert_eq!(digits.len(), self.level, "level mismatch");
+        let len = digits.first().map_or(0, |d| d.len());
+        (0..len)
+            .map(|i| {
+                digits
+                    .iter()
+                    .enumerate()
+                    .fold(Torus::ZERO, |acc, (j, d)| acc + self.gadget(j) * d[i])
+            })
+            .collect()
+    }
+
+    pub fn recompose_polynomial<const N: usize>(
+        &self,
+        digits: &[IntPolynomial<N>],
+    ) -> TorusPolynomial<N> {
+        assert_eq!(digits.len(), self.level, "level mismatch");
+        digits
+            .iter()
+            .enumerate()
+            .fold(TorusPolynomial::ZERO, |acc, (j, d)| {
+                acc + TorusPolynomial::new(d.coefs.map(|c| self.gadget(j) * c))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::TorusRng;
+
+    const PARAMS: [(u32, usize); 6] = [(1, 1), (2, 8), (6, 3), (8, 4), (10, 2), (16, 2)];
+
+    fn error(a: Torus, b: Torus) -> TorusRepr {
+        (a - b).abs_distance_to_zero()
+    }
+
+    fn check(decomposer: &GadgetDecomposer, t: Torus) {
+        let digits = decomposer.decompose(t);
+        let half = 1 << (decomposer.base_log - 1);
+        assert!(
+            digits.iter().all(|&d| -half <= d && d < half),
+            "{:?}",
+            digits
+        );
+        assert!(error(decomposer.recompose(&digits), t) <= decomposer.max_error());
+    }
+
+    #[test]
+    fn test_edge_values() {
+        for (base_log, level) in PARAMS {
+            let decomposer = GadgetDecomposer::new(base_log, level);
+            for inner in [0, 1, 1 << 31, (1 << 31) - 1, u32::MAX, 0x1234_5678] {
+                check(&decomposer, Torus::new(inner));
+            }
+        }
+    }
+
+    #[test]
+    fn test_exact_representable() {
+        // multiples of Bg^-l are recomposed exactly
+        let decomposer = GadgetDecomposer::new(6, 3);
+        for k in [0u32, 1, 17, (1 << 18) - 1] {
+            let t = Torus::new(k << 14);
+            assert_eq!(decomposer.recompose(&decomposer.decompose(t)), t);
+        }
+    }
+
+    #[test]
+    fn test_full_precision() {
+        let decomposer = GadgetDecomposer::new(8, 4);
+        assert_eq!(decomposer.max_error(), 0);
+        let t = Torus::new(0xdead_beef);
+        assert_eq!(decomposer.recompose(&decomposer.decompose(t)), t);
+    }
+
+    #[test]
+    fn test_slice_and_polynomial() {
+        let decomposer = GadgetDecomposer::new(6, 3);
+        let p = TorusPolynomial::new([0, 1 << 31, u32::MAX, 0x1234_5678].map(Torus::new));
+
+        let slice_digits = decomposer.decompose_slice(&p.coefs);
+        let poly_digits = decomposer.decompose_polynomial(&p);
+        for j in 0..3 {
+            assert_eq!(slice_digits[j], poly_digits[j].coefs);
+        }
+        for (i, &t) in p.coefs.iter().enumerate() {
+            let digits: Vec<i32> = (0..3).map(|j| slice_digits[j][i]).collect();
+            assert_eq!(digits, decomposer.decompose(t));
+        }
+
+        let recomposed = decomposer.recompose_polynomial(&poly_digits);
+        assert_eq!(
+            recomposed.coefs.to_vec(),
+            decomposer.recompose_slice(&slice_digits)
+        );
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_random_recompose_error() {
+        use distr_traits::uniform::UniformSample;
+        use rand::Rng;
+
+        let mut rng = TorusRng::from_seed([1; 32]);
+        for (base_log, level) in PARAMS {
+            let decomposer = GadgetDecomposer::new(base_log, level);
+            for _ in 0..1000 {
+                check(&decomposer, Torus::new(rng.gen()));
+                check(&decomposer, Torus::uniform_sample(&mut rng));
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_invalid() {
+        let decomposer = GadgetDecomposer::new(8, 4);
+        let json = serde_json::to_string(&decomposer).unwrap();
+        assert_eq!(
+            serde_json::from_str::<GadgetDecomposer>(&json).unwrap(),
+            decomposer
+        );
+        for invalid in [
+            r#"{"base_log":2,"level":0}"#,
+            r#"{"base_log":0,"level":2}"#,
+            r#"{"base_log":8,"level":5}"#,
+            r#"{"base_log":4294967295,"level":2}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<GadgetDecomposer>(invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{Torus, TorusRepr};
+
+/// Encodes m in Z_p as the torus value closest to m / (p * 2^padding_bits).
+///
+/// The padding bits keep the top of the torus free, so sums of encodings that
+/// exceed p are still decoded exactly (as a value in [0, p * 2^padding_bits)).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "EncoderRaw"))]
+pub struct Encoder {
+    pub modulus: u32,
+    pub padding_bits: u32,
+}
+
+/// Unchecked fields of a deserialized [`Encoder`]
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct EncoderRaw {
+    modulus: u32,
+    padding_bits: u32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<EncoderRaw> for Encoder {
+    type Error = &'static str;
+
+    fn try_from(raw: EncoderRaw) -> Result<Self, Self::Error> {
+        Self::check(raw.modulus, raw.padding_bits)?;
+        Ok(Self::new(raw.modulus, raw.padding_bits))
+    }
+}
+
+/// Result of decoding a noisy torus value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decoded {
+    pub message: u32,
+    /// t - encode(message), in units of 2^-32
+    pub error: i64,
+    /// Distance from t to the closest decision boundary, in units of 2^-32.
+    /// Values close to zero mean the decoding almost failed.
+    pub margin: u64,
+}
+
+impl Encoder {
+    pub fn new(modulus: u32, padding_bits: u32) -> Self {
+        if let Err(e) = Self::check(modulus, padding_bits) {
+            panic!("{}", e);
+        }
+        Self {
+            modulus,
+            padding_bits,
+        }
+    }
+
+    /// Why new(modulus, padding_bits) would panic, if it does
+    fn check(modulus: u32, padding_bits: u32) -> Result<(), &'static str> {
+        if modulus == 0 {
+            return Err("modulus must be positive");
+        }
+        // checked first, so the shift below cannot overflow
+        if padding_bits > TorusRepr::BITS || (modulus as u64) << padding_bits > 1 << TorusRepr::BITS
+        {
+            return Err("message space exceeds torus precision");
+        }
+        Ok(())
+    }
+
+    /// 2^(32 - padding_bits), the width of the torus used by messages
+    fn width_log(&self) -> u32 {
+        TorusRepr::BITS - self.padding_bits
+    }
+
+    /// Number of distinct decoded values, p * 2^padding_bits
+    pub fn space(&self) -> u64 {
+        (self.modulus as u64) << self.padding_bits
+    }
+
+    /// round(m * 2^(32 - padding_bits) / p), m taken mod p * 2^padding_bits
+    pub fn encode(&self, m: u32) -> Torus {
+        let m = m as u128 % self.space() as u128;
+        let p = self.modulus as u128;
+        let inner = ((m << self.width_log()) + p / 2) / p;
+        Torus::new(inner as TorusRepr)
+    }
+
+    /// Round t to the closest encoding
+    pub fn decode(&self, t: Torus) -> u32 {
+        self.decode_with_margin(t).message
+    }
+
+    pub fn decode_with_margin(&self, t: Torus) -> Decoded {
+        let p = self.modulus as i128;
+        let x = t.inner() as i128;
+        let width = 1i128 << self.width_log();
+
+        // round(x * p / width), before reduction
+        let m = (x * p + width / 2) >> self.width_log();
+        let message = (m % self.space() as i128) as u32;
+
+        // width / 2p - |x - m * width / p|, scaled by 2p
+        let offset = 2 * x * p - 2 * m * width;
+        let margin = (width - offset.abs()) / (2 * p);
+
+        let error = Torus::distance(t, self.encode(message)) as i64;
+        Decoded {
+            message,
+            error,
+            margin: margin as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_power_of_two() {
+        let encoder = Encoder::new(8, 0);
+        for m in 0..8 {
+            assert_eq!(encoder.encode(m), Torus::new(m << 29));
+        }
+        assert_eq!(Encoder::new(4, 1).encode(1), Torus::new(1 << 29));
+        assert_eq!(Encoder::new(1 << 31, 1).encode(3), Torus::new(3));
+    }
+
+    #[test]
+    fn test_encode_odd_modulus() {
+        let encoder = Encoder::new(3, 0);
+        // 2^32 / 3 = 1431655765.33
+        assert_eq!(encoder.encode(1), Torus::new(1_431_655_765));
+        // 2^33 / 3 = 2863311530.67
+        assert_eq!(encoder.encode(2), Torus::new(2_863_311_531));
+        for m in 0..3 {
+            assert_eq!(encoder.decode(encoder.encode(m)), m);
+        }
+    }
+
+    #[test]
+    fn test_decode_noisy() {
+        let encoder = Encoder::new(5, 0);
+        for m in 0..5 {
+            let t = encoder.encode(m);
+            for noise in [-400_000_000, -1, 0, 1, 400_000_000] {
+                let decoded = encoder.decode_with_margin(t + Torus::new(noise as u32));
+                assert_eq!(decoded.message, m);
+                assert_eq!(decoded.error, noise);
+            }
+        }
+    }
+
+    #[test]
+    fn test_margin() {
+        let encoder = Encoder::new(4, 0);
+        let exact = encoder.decode_with_margin(Torus::new(1 << 30));
+        assert_eq!(exact.margin, 1 << 29);
+
+        // just below the boundary 1/8 between 0 and 1/4
+        let near = encoder.decode_with_margin(Torus::new((1 << 29) - 10));
+        assert_eq!(near.message, 0);
+        assert_eq!(near.margin, 10);
+
+        // wraps around to 0 from below
+        let wrapped = encoder.decode_with_margin(Torus::new(u32::MAX));
+        assert_eq!(wrapped.message, 0);
+        assert_eq!(wrapped.error, -1);
+        assert_eq!(wrapped.margin, (1 << 29) - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "message space exceeds torus precision")]
+    fn test_too_many_padding_bits() {
+        Encoder::new(1 << 31, 33);
+    }
+
+    #[test]
+    fn test_full_padding() {
+        let encoder = Encoder::new(1, 32);
+        assert_eq!(encoder.space(), 1 << 32);
+        assert_eq!(encoder.encode(5), Torus::new(5));
+    }
+
+    #[test]
+    fn test_padding_carry() {
+        let encoder = Encoder::new(4, 1);
+        assert_eq!(encoder.space(), 8);
+        let sum = encoder.encode(3) + encoder.encode(2);
+        assert_eq!(encoder.decode(sum), 5);
+        assert_eq!(encoder.decode(sum) % encoder.modulus, 1);
+    }
+
+    #[test]
+    fn test_add_odd_modulus() {
+        let encoder = Encoder::new(7, 0);
+        for a in 0..7 {
+            for b in 0..7 {
+                let sum = encoder.encode(a) + encoder.encode(b);
+                assert_eq!(encoder.decode(sum), (a + b) % 7);
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_invalid() {
+        let encoder = Encoder::new(3, 30);
+        let json = serde_json::to_string(&encoder).unwrap();
+        assert_eq!(serde_json::from_str::<Encoder>(&json).unwrap(), encoder);
+        for invalid in [
+            r#"{"modulus":0,"padding_bits":1}"#,
+            r#"{"modulus":3,"padding_bits":31}"#,
+            r#"{"modulus":1,"padding_bits":33}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<Encoder>(invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
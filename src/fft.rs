@@ -0,0 +1,317 @@
+//! Negacyclic polynomial multiplication with a complex FFT over f64.
+//!
+//! A polynomial of Z[X]/(X^N+1) is folded into N/2 complex numbers
+//! c_j = (a_j + i a_{j+N/2}) * w^j with w = exp(iπ/N), so that a cyclic FFT
+//! of size N/2 evaluates it on the roots of X^N+1.
+//!
+//! Torus coefficients are lifted to signed integers in [-2^31, 2^31) before
+//! the transform. For an integer polynomial with coefficients bounded by B,
+//! the coefficients of the exact product are bounded by N * B * 2^31, and the
+//! float error per coefficient is about N * B * 2^31 * 2^-53 * log2(N).
+//! With N = 1024 and B = 2^9 (typical gadget digits) this is a handful of
+//! units of 2^-32, see [`FftPlan::ERROR_BOUND_LOG2`] and the tests.
+
+use crate::polynomial::{IntPolynomial, PolynomialMultiplier, TorusPolynomial};
+use crate::Torus;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    const ZERO: Complex = Complex { re: 0., im: 0. };
+
+    fn from_angle(theta: f64) -> Complex {
+        Complex {
+            re: theta.cos(),
+            im: theta.sin(),
+        }
+    }
+
+    fn conj(self) -> Complex {
+        Complex {
+            re: self.re,
+            im: -self.im,
+        }
+    }
+}
+
+impl std::ops::Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+}
+
+impl std::ops::Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+}
+
+impl std::ops::Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+/// Polynomial of degree < N in the Fourier domain (N/2 complex values)
+#[derive(Clone, Debug, PartialEq)]
+pub struct FourierPolynomial<const N: usize> {
+    coefs: Vec<Complex>,
+}
+
+impl<const N: usize> FourierPolynomial<N> {
+    pub fn zero() -> Self {
+        Self {
+            coefs: vec![Complex::ZERO; N / 2],
+        }
+    }
+
+    /// self += a * b, pointwise
+    pub fn mul_add_assign(&mut self, a: &Self, b: &Self) {
+        for ((c, &x), &y) in self.coefs.iter_mut().zip(&a.coefs).zip(&b.coefs) {
+            *c = *c + x * y;
+        }
+    }
+}
+
+impl<const N: usize> std::ops::Mul for &FourierPolynomial<N> {
+    type Output = FourierPolynomial<N>;
+
+    fn mul(self, rhs: &FourierPolynomial<N>) -> FourierPolynomial<N> {
+        let mut out = FourierPolynomial::zero();
+        out.mul_add_assign(self, rhs);
+        out
+    }
+}
+
+/// Precomputed twiddle factors for negacyclic products of size N.
+/// Build once and reuse across multiplications.
+#[derive(Clone, Debug)]
+pub struct FftPlan<const N: usize> {
+    /// w^j = exp(iπj/N) for j < N/2
+    twist: Vec<Complex>,
+    /// exp(-2πik/(N/2)) for k < N/4
+    roots: Vec<Complex>,
+    bit_reverse: Vec<usize>,
+}
+
+impl<const N: usize> Default for FftPlan<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FftPlan<N> {
+    /// log2 of the documented per-coefficient error bound (in units of 2^-32)
+    /// for N <= 2048 and integer coefficients in [-2^9, 2^9].
+    pub const ERROR_BOUND_LOG2: u32 = 4;
+
+    pub fn new() -> Self {
+        assert!(N >= 2 && N.is_power_of_two(), "N must be a power of two");
+
+        let m = N / 2;
+        let pi = std::f64::consts::PI;
+        let twist = (0..m)
+            .map(|j| Complex::from_angle(pi * j as f64 / N as f64))
+            .collect();
+        let roots = (0..m / 2)
+            .map(|k| Complex::from_angle(-2. * pi * k as f64 / m as f64))
+            .collect();
+
+        let bits = m.trailing_zeros();
+        let bit_reverse = (0..m)
+            .map(|i| {
+                if bits == 0 {
+                    0
+                } else {
+                    i.reverse_bits() >> (usize::BITS - bits)
+                }
+            })
+            .collect();
+
+        Self {
+            twist,
+            roots,
+            bit_reverse,
+        }
+    }
+
+    /// In place radix-2 FFT of size N/2, unnormalized.
+    fn fft(&self, buf: &mut [Complex], inverse: bool) {
+        let m = buf.len();
+        for (i, &j) in self.bit_reverse.iter().enumerate() {
+            if i < j {
+                buf.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= m {
+            let half = len / 2;
+            let step = m / len;
+            for start in (0..m).step_by(len) {
+                for k in 0..half {
+                    let w = self.roots[k * step];
+                    let w = if inverse { w.conj() } else { w };
+                    let u = buf[start + k];
+                    let v = buf[start + k + half] * w;
+                    buf[start + k] = u + v;
+                    buf[start + k + half] = u - v;
+                }
+            }
+            len *= 2;
+        }
+    }
+
+    fn forward(&self, coefs: impl Fn(usize) -> f64) -> FourierPolynomial<N> {
+        let m = N / 2;
+        let mut buf: Vec<Complex> = (0..m)
+            .map(|j| {
+                Complex {
+                    re: coefs(j),
+                    im: coefs(j + m),
+                } * self.twist[j]
+            })
+            .collect();
+        self.fft(&mut buf, false);
+        FourierPolynomial { coefs: buf }
+    }
+
+    pub fn forward_int(&self, p: &IntPolynomial<N>) -> FourierPolynomial<N> {
+        self.forward(|j| p.coefs[j] as f64)
+    }
+
+    pub fn forward_torus(&self, p: &TorusPolynomial<N>) -> FourierPolynomial<N> {
+        self.forward(|j| p.coefs[j].to_signed() as f64)
+    }
+
+    /// Back to the torus, rounding every coefficient and reducing mod 2^32
+    pub fn backward_torus(&self, p: &FourierPolynomial<N>) -> TorusPolynomial<N> {
+        let m = N / 2;
+        let mut buf = p.coefs.clone();
+        self.fft(&mut buf, true);
+
+        let mut out = TorusPolynomial::<N>::new([Torus::new(0); N]);
+        let scale = 1. / m as f64;
+        for (j, &c) in buf.iter().enumerate() {
+            let c = c * self.twist[j].conj();
+            out.coefs[j] = Torus::new((c.re * scale).round() as i64 as u32);
+            out.coefs[j + m] = Torus::new((c.im * scale).round() as i64 as u32);
+        }
+        out
+    }
+}
+
+impl<const N: usize> PolynomialMultiplier<N> for FftPlan<N> {
+    fn mul(&self, a: &IntPolynomial<N>, b: &TorusPolynomial<N>) -> TorusPolynomial<N> {
+        let fa = self.forward_int(a);
+        let fb = self.forward_torus(b);
+        self.backward_torus(&(&fa * &fb))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::TorusRng;
+    use distr_traits::uniform::UniformSample;
+    use rand::Rng;
+
+    fn max_error<const N: usize>(a: &TorusPolynomial<N>, b: &TorusPolynomial<N>) -> u32 {
+        a.coefs
+            .iter()
+            .zip(&b.coefs)
+            .map(|(&x, &y)| (x - y).abs_distance_to_zero())
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_small_exact() {
+        let plan = FftPlan::<4>::new();
+        let a = IntPolynomial::new([1, 2, -3, 4]);
+        let b = TorusPolynomial::new([5, 6, 7, u32::MAX].map(Torus::new));
+        assert_eq!(plan.mul(&a, &b), b * a);
+    }
+
+    #[test]
+    fn test_monomial_exact() {
+        let plan = FftPlan::<16>::new();
+        let b = TorusPolynomial::new(std::array::from_fn(|i| Torus::new(i as u32 * 1000)));
+        for k in 0..16 {
+            let mut a = IntPolynomial::<16>::new([0; 16]);
+            a[k] = 1;
+            assert_eq!(plan.mul(&a, &b), b.mul_by_monomial(k));
+        }
+    }
+
+    #[test]
+    fn test_degree_two() {
+        let plan = FftPlan::<2>::new();
+        let a = IntPolynomial::new([2, 1]);
+        let b = TorusPolynomial::new([1 << 29, 1 << 30].map(Torus::new));
+        assert_eq!(plan.mul(&a, &b), b * a);
+    }
+
+    #[cfg(feature = "random")]
+    fn random_error<const N: usize>(plan: &FftPlan<N>, digit_bound: i32, seed: u8) -> u32 {
+        let mut rng = TorusRng::from_seed([seed; 32]);
+        let a = IntPolynomial::new(std::array::from_fn(|_| {
+            rng.gen_range(-digit_bound..=digit_bound)
+        }));
+        let b = TorusPolynomial::new(std::array::from_fn(|_| Torus::uniform_sample(&mut rng)));
+        max_error(&plan.mul(&a, &b), &b.naive_mul(&a))
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_random_1024_error_bound() {
+        let plan = FftPlan::<1024>::new();
+        for seed in 0..4 {
+            let err = random_error(&plan, 1 << 9, seed);
+            assert!(
+                err <= 1 << FftPlan::<1024>::ERROR_BOUND_LOG2,
+                "error {}",
+                err
+            );
+        }
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_random_2048_error_bound() {
+        let plan = FftPlan::<2048>::new();
+        let err = random_error(&plan, 1 << 9, 1);
+        assert!(
+            err <= 1 << FftPlan::<2048>::ERROR_BOUND_LOG2,
+            "error {}",
+            err
+        );
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_random_binary_exact() {
+        // binary polynomials (secret keys) stay well inside f64 precision
+        let plan = FftPlan::<1024>::new();
+        assert_eq!(random_error(&plan, 1, 1), 0);
+    }
+}
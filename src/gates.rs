@@ -0,0 +1,389 @@
+//! Homomorphic boolean gates.
+//!
+//! A bit is encoded as +1/8 (true) or -1/8 (false) on the torus. Every binary
+//! gate is a linear combination of its inputs followed by a gate bootstrapping
+//! (sign extraction) and a key switching back to the LWE key.
+
+use crate::bootstrap::BootstrappingKey;
+use crate::decomposition::GadgetDecomposer;
+use crate::fft::FftPlan;
+use crate::keyswitch::KeySwitchingKey;
+#[cfg(feature = "random")]
+use crate::lwe::Seed;
+use crate::lwe::{LweCiphertext, LweSecretKey};
+#[cfg(feature = "random")]
+use crate::rng::TorusRng;
+use crate::trlwe::TrlweSecretKey;
+use crate::Torus;
+
+/// 1/8
+const MU: Torus = Torus::new(1 << 29);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GateParameters {
+    pub lwe_dimension: usize,
+    /// noise of fresh samples and of the key switching key
+    pub lwe_std: f64,
+    /// number of TRLWE mask polynomials
+    pub k: usize,
+    /// noise of the bootstrapping key
+    pub trlwe_std: f64,
+    pub bootstrap_decomposer: GadgetDecomposer,
+    pub keyswitch_decomposer: GadgetDecomposer,
+}
+
+impl GateParameters {
+    /// Default gate bootstrapping parameters of the TFHE library, for N = 1024
+    pub const TFHE_LIB: GateParameters = GateParameters {
+        lwe_dimension: 500,
+        lwe_std: 2.44e-5,
+        k: 1,
+        trlwe_std: 7.18e-9,
+        bootstrap_decomposer: GadgetDecomposer {
+            base_log: 10,
+            level: 2,
+        },
+        keyswitch_decomposer: GadgetDecomposer {
+            base_log: 2,
+            level: 8,
+        },
+    };
+}
+
+/// Secret keys, needed to encrypt and decrypt
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientKey<const N: usize> {
+    params: GateParameters,
+    lwe_key: LweSecretKey,
+    #[cfg_attr(not(feature = "random"), allow(dead_code))]
+    trlwe_key: TrlweSecretKey<N>,
+}
+
+/// Public evaluation keys, needed to evaluate gates
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerKey<const N: usize> {
+    bootstrapping_key: BootstrappingKey<N>,
+    keyswitching_key: KeySwitchingKey,
+    /// recomputed on deserialization
+    #[cfg_attr(feature = "serde", serde(skip))]
+    mul: FftPlan<N>,
+}
+
+impl<const N: usize> ClientKey<N> {
+    #[cfg(feature = "random")]
+    pub fn generate(params: GateParameters, state: &mut impl rand::Rng) -> Self {
+        Self {
+            params,
+            lwe_key: LweSecretKey::generate(params.lwe_dimension, state),
+            trlwe_key: TrlweSecretKey::generate(params.k, state),
+        }
+    }
+
+    /// Same key for the same seed, each secret key from its own fork
+    #[cfg(feature = "random")]
+    pub fn from_seed(params: GateParameters, seed: Seed) -> Self {
+        let rng = TorusRng::from_seed(seed);
+        Self {
+            params,
+            lwe_key: LweSecretKey::generate(params.lwe_dimension, &mut rng.fork("lwe key")),
+            trlwe_key: TrlweSecretKey::generate(params.k, &mut rng.fork("trlwe key")),
+        }
+    }
+
+    pub fn params(&self) -> &GateParameters {
+        &self.params
+    }
+
+    pub fn lwe_key(&self) -> &LweSecretKey {
+        &self.lwe_key
+    }
+
+    #[cfg(feature = "random")]
+    pub fn encrypt(&self, bit: bool, state: &mut impl rand::Rng) -> LweCiphertext {
+        let m = if bit { MU } else { -MU };
+        self.lwe_key.encrypt(m, self.params.lwe_std, state)
+    }
+
+    pub fn decrypt(&self, ct: &LweCiphertext) -> bool {
+        self.lwe_key.decrypt_phase(ct).sign() == 1
+    }
+}
+
+impl<const N: usize> ServerKey<N> {
+    #[cfg(feature = "random")]
+    pub fn generate(client_key: &ClientKey<N>, state: &mut impl rand::Rng) -> Self {
+        let params = client_key.params;
+        let mul = FftPlan::new();
+        let bootstrapping_key = BootstrappingKey::generate(
+            &client_key.lwe_key,
+            &client_key.trlwe_key,
+            params.bootstrap_decomposer,
+            params.trlwe_std,
+            &mul,
+            state,
+        );
+        let keyswitching_key = KeySwitchingKey::generate(
+            &client_key.trlwe_key.to_lwe_key(),
+            &client_key.lwe_key,
+            params.keyswitch_decomposer,
+            params.lwe_std,
+            state,
+        );
+        Self {
+            bootstrapping_key,
+            keyswitching_key,
+            mul,
+        }
+    }
+
+    /// Same key for the same client key and seed, the masks and the noise
+    /// of each key from their own forks
+    #[cfg(feature = "random")]
+    pub fn from_seed(client_key: &ClientKey<N>, seed: Seed) -> Self {
+        let rng = TorusRng::from_seed(seed);
+        let params = client_key.params;
+        let mul = FftPlan::new();
+        let bootstrapping_key = BootstrappingKey::generate_with_streams(
+            &client_key.lwe_key,
+            &client_key.trlwe_key,
+            params.bootstrap_decomposer,
+            params.trlwe_std,
+            &mul,
+            &mut rng.fork("bk mask"),
+            &mut rng.fork("bk noise"),
+        );
+        let keyswitching_key = KeySwitchingKey::generate_with_streams(
+            &client_key.trlwe_key.to_lwe_key(),
+            &client_key.lwe_key,
+            params.keyswitch_decomposer,
+            params.lwe_std,
+            &mut rng.fork("ksk mask"),
+            &mut rng.fork("ksk noise"),
+        );
+        Self {
+            bootstrapping_key,
+            keyswitching_key,
+            mul,
+        }
+    }
+
+    /// Noiseless encryption of a known bit
+    pub fn constant(&self, bit: bool) -> LweCiphertext {
+        let m = if bit { MU } else { -MU };
+        LweCiphertext::trivial(m, self.bootstrapping_key.dimension())
+    }
+
+    /// +1/8 if the phase is in [0, 1/2), -1/8 otherwise, under the extracted key
+    fn bootstrap_without_keyswitch(&self, ct: &LweCiphertext) -> LweCiphertext {
+        self.bootstrapping_key.bootstrap(ct, |_| MU, &self.mul)
+    }
+
+    fn bootstrap(&self, ct: &LweCiphertext) -> LweCiphertext {
+        let out = self.bootstrap_without_keyswitch(ct);
+        self.keyswitching_key.keyswitch(&out)
+    }
+
+    /// (0, offset) + sum of the inputs
+    fn linear(&self, offset: Torus, terms: &[(i32, &LweCiphertext)]) -> LweCiphertext {
+        let mut ct = LweCiphertext::trivial(offset, self.bootstrapping_key.dimension());
+        for &(coef, term) in terms {
+            ct += &(term * coef);
+        }
+        ct
+    }
+
+    pub fn not(&self, a: &LweCiphertext) -> LweCiphertext {
+        -a
+    }
+
+    pub fn nand(&self, a: &LweCiphertext, b: &LweCiphertext) -> LweCiphertext {
+        self.bootstrap(&self.linear(MU, &[(-1, a), (-1, b)]))
+    }
+
+    pub fn and(&self, a: &LweCiphertext, b: &LweCiphertext) -> LweCiphertext {
+        self.bootstrap(&self.linear(-MU, &[(1, a), (1, b)]))
+    }
+
+    pub fn or(&self, a: &LweCiphertext, b: &LweCiphertext) -> LweCiphertext {
+        self.bootstrap(&self.linear(MU, &[(1, a), (1, b)]))
+    }
+
+    pub fn xor(&self, a: &LweCiphertext, b: &LweCiphertext) -> LweCiphertext {
+        self.bootstrap(&self.linear(MU + MU, &[(2, a), (2, b)]))
+    }
+
+    pub fn xnor(&self, a: &LweCiphertext, b: &LweCiphertext) -> LweCiphertext {
+        self.bootstrap(&self.linear(-MU - MU, &[(-2, a), (-2, b)]))
+    }
+
+    /// if a { b } else { c }
+    pub fn mux(&self, a: &LweCiphertext, b: &LweCiphertext, c: &LweCiphertext) -> LweCiphertext {
+        let a_and_b = self.bootstrap_without_keyswitch(&self.linear(-MU, &[(1, a), (1, b)]));
+        let not_a_and_c = self.bootstrap_without_keyswitch(&self.linear(-MU, &[(-1, a), (1, c)]));
+
+        let mut out = LweCiphertext::trivial(MU, a_and_b.dimension());
+        out += &a_and_b;
+        out += &not_a_and_c;
+        self.keyswitching_key.keyswitch(&out)
+    }
+}
+
+#[cfg(all(test, feature = "random"))]
+mod tests {
+    use super::*;
+
+    const N: usize = 256;
+
+    /// Small and insecure, only to keep the tests fast
+    const TEST_PARAMS: GateParameters = GateParameters {
+        lwe_dimension: 64,
+        lwe_std: 1e-5,
+        k: 1,
+        trlwe_std: 1e-9,
+        bootstrap_decomposer: GadgetDecomposer {
+            base_log: 8,
+            level: 2,
+        },
+        keyswitch_decomposer: GadgetDecomposer {
+            base_log: 2,
+            level: 8,
+        },
+    };
+
+    fn keys() -> (ClientKey<N>, ServerKey<N>) {
+        let client_key = ClientKey::from_seed(TEST_PARAMS, [1; 32]);
+        let server_key = ServerKey::from_seed(&client_key, [1; 32]);
+        (client_key, server_key)
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let mut rng = TorusRng::from_seed([2; 32]);
+        let client_key = ClientKey::<N>::generate(TEST_PARAMS, &mut rng);
+        for bit in [false, true] {
+            assert_eq!(client_key.decrypt(&client_key.encrypt(bit, &mut rng)), bit);
+        }
+    }
+
+    #[test]
+    fn test_from_seed() {
+        let client_key = ClientKey::<N>::from_seed(TEST_PARAMS, [3; 32]);
+        let again = ClientKey::<N>::from_seed(TEST_PARAMS, [3; 32]);
+        assert_eq!(client_key.lwe_key(), again.lwe_key());
+        assert_eq!(client_key.trlwe_key, again.trlwe_key);
+        let other = ClientKey::<N>::from_seed(TEST_PARAMS, [4; 32]);
+        assert_ne!(client_key.lwe_key(), other.lwe_key());
+
+        // the LWE key does not depend on the TRLWE parameters
+        let params = GateParameters {
+            k: 2,
+            ..TEST_PARAMS
+        };
+        let wider = ClientKey::<N>::from_seed(params, [3; 32]);
+        assert_eq!(client_key.lwe_key(), wider.lwe_key());
+    }
+
+    #[test]
+    fn test_binary_gates() {
+        type Gate = fn(&ServerKey<N>, &LweCiphertext, &LweCiphertext) -> LweCiphertext;
+        type TruthTable = fn(bool, bool) -> bool;
+
+        let mut rng = TorusRng::from_seed([3; 32]);
+        let (client_key, server_key) = keys();
+        let gates: [(&str, Gate, TruthTable); 5] = [
+            ("nand", ServerKey::nand, |a, b| !(a && b)),
+            ("and", ServerKey::and, |a, b| a && b),
+            ("or", ServerKey::or, |a, b| a || b),
+            ("xor", ServerKey::xor, |a, b| a ^ b),
+            ("xnor", ServerKey::xnor, |a, b| !(a ^ b)),
+        ];
+
+        for (name, gate, expected) in gates {
+            for a in [false, true] {
+                for b in [false, true] {
+                    let ca = client_key.encrypt(a, &mut rng);
+                    let cb = client_key.encrypt(b, &mut rng);
+                    let out = gate(&server_key, &ca, &cb);
+                    assert_eq!(
+                        client_key.decrypt(&out),
+                        expected(a, b),
+                        "{}({}, {})",
+                        name,
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_not_and_constant() {
+        let mut rng = TorusRng::from_seed([4; 32]);
+        let (client_key, server_key) = keys();
+        for a in [false, true] {
+            let ca = client_key.encrypt(a, &mut rng);
+            assert_eq!(client_key.decrypt(&server_key.not(&ca)), !a);
+            assert_eq!(client_key.decrypt(&server_key.constant(a)), a);
+        }
+    }
+
+    #[test]
+    fn test_mux() {
+        let mut rng = TorusRng::from_seed([5; 32]);
+        let (client_key, server_key) = keys();
+        for a in [false, true] {
+            for b in [false, true] {
+                for c in [false, true] {
+                    let ca = client_key.encrypt(a, &mut rng);
+                    let cb = client_key.encrypt(b, &mut rng);
+                    let cc = client_key.encrypt(c, &mut rng);
+                    let out = server_key.mux(&ca, &cb, &cc);
+                    let expected = if a { b } else { c };
+                    assert_eq!(
+                        client_key.decrypt(&out),
+                        expected,
+                        "mux({}, {}, {})",
+                        a,
+                        b,
+                        c
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_chained_gates() {
+        // bootstrapped outputs can be fed to further gates
+        let mut rng = TorusRng::from_seed([6; 32]);
+        let (client_key, server_key) = keys();
+        let a = client_key.encrypt(true, &mut rng);
+        let b = client_key.encrypt(false, &mut rng);
+
+        let mut acc = server_key.xor(&a, &b);
+        for _ in 0..4 {
+            acc = server_key.nand(&acc, &a);
+            acc = server_key.not(&acc);
+        }
+        assert!(client_key.decrypt(&acc));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_keys() {
+        let mut rng = TorusRng::from_seed([7; 32]);
+        let (client_key, server_key) = keys();
+        let client_key: ClientKey<N> =
+            serde_json::from_str(&serde_json::to_string(&client_key).unwrap()).unwrap();
+        let server_key: ServerKey<N> =
+            bincode::deserialize(&bincode::serialize(&server_key).unwrap()).unwrap();
+
+        let a = client_key.encrypt(true, &mut rng);
+        let b = client_key.encrypt(true, &mut rng);
+        assert!(!client_key.decrypt(&server_key.nand(&a, &b)));
+    }
+}
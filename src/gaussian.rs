@@ -0,0 +1,371 @@
+//! Discrete Gaussian sampling on the 2^-32 torus grid.
+//!
+//! [`NormalSample`] for [`Torus`] rounds a continuous normal sample through
+//! f64, which is biased for small standard deviations. [`DiscreteGaussian`]
+//! samples D_{Z,σ} directly, with σ = std * 2^32 grid steps:
+//!
+//! * for σ up to [`MAX_TABLE_SIGMA`], by inversion of a cumulative
+//!   distribution table (CDT) with 64-bit precision, cut at 13σ;
+//! * above, as x1 + k x2 with x1, x2 drawn from a table of width
+//!   σ / sqrt(1 + k^2), which is statistically close to D_{Z,σ} since that
+//!   width is far above the smoothing parameter of Z.
+//!
+//! [`DiscreteNormal`] wraps it as an alternative [`NormalSample`] strategy.
+
+use crate::polynomial::TorusPolynomial;
+use crate::{Torus, TorusRepr};
+use distr_traits::normal::NormalSample;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Largest width sampled from a single table, in grid steps
+pub const MAX_TABLE_SIGMA: f64 = 1024.;
+
+/// Tail cut of the tables, in multiples of σ
+const TAIL_CUT: f64 = 13.;
+
+/// Inversion table of |x| for x ~ D_{Z,σ}
+#[derive(Clone, Debug, PartialEq)]
+struct Cdt {
+    /// cdf[x] = P(|X| <= x) * 2^64, with the last entry saturated
+    cdf: Vec<u64>,
+}
+
+impl Cdt {
+    fn new(sigma: f64) -> Self {
+        if sigma == 0. {
+            return Self {
+                cdf: vec![u64::MAX],
+            };
+        }
+        let len = (TAIL_CUT * sigma).ceil() as usize + 1;
+        let weights: Vec<f64> = (0..len)
+            .map(|x| {
+                let rho = (-((x * x) as f64) / (2. * sigma * sigma)).exp();
+                if x == 0 {
+                    rho
+                } else {
+                    2. * rho
+                }
+            })
+            .collect();
+        let total: f64 = weights.iter().sum();
+
+        let scale = 2f64.powi(64);
+        let mut acc = 0.;
+        let mut cdf: Vec<u64> = weights
+            .iter()
+            .map(|w| {
+                acc += w;
+                // float to int casts saturate
+                (acc / total * scale) as u64
+            })
+            .collect();
+        *cdf.last_mut().unwrap() = u64::MAX;
+        Self { cdf }
+    }
+
+    fn sample(&self, state: &mut impl rand::Rng) -> i64 {
+        let r: u64 = state.gen();
+        let x = self
+            .cdf
+            .partition_point(|&c| c <= r)
+            .min(self.cdf.len() - 1) as i64;
+        // drawn even for x = 0, so the randomness used does not depend on x
+        if state.next_u32() >> 31 == 1 {
+            -x
+        } else {
+            x
+        }
+    }
+
+    /// Same result as [`Self::sample`], by a branch-free scan of the whole
+    /// table instead of a binary search
+    #[cfg(feature = "constant_time")]
+    fn sample_ct(&self, state: &mut impl rand::Rng) -> i64 {
+        use subtle::{
+            Choice, ConditionallyNegatable, ConditionallySelectable, ConstantTimeGreater,
+        };
+
+        let r: u64 = state.gen();
+        let mut x = 0u64;
+        for c in &self.cdf {
+            x += u64::from((!c.ct_gt(&r)).unwrap_u8());
+        }
+        let last = (self.cdf.len() - 1) as u64;
+        let mut x = u64::conditional_select(&x, &last, x.ct_gt(&last)) as i64;
+        x.conditional_negate(Choice::from((state.next_u32() >> 31) as u8));
+        x
+    }
+}
+
+/// Sampler of D_{Z,σ} on the torus grid, built once for a standard deviation
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiscreteGaussian {
+    std: f64,
+    table: Cdt,
+    /// 1 if the table is sampled directly, else x1 + k x2
+    k: i64,
+}
+
+impl DiscreteGaussian {
+    /// std is the standard deviation on the torus, as for [`NormalSample`]
+    pub fn new(std: f64) -> Self {
+        assert!(std.is_finite() && std >= 0., "invalid standard deviation");
+        let sigma = std * 2f64.powi(TorusRepr::BITS as i32);
+        if sigma <= MAX_TABLE_SIGMA {
+            return Self {
+                std,
+                table: Cdt::new(sigma),
+                k: 1,
+            };
+        }
+        let k = (sigma / MAX_TABLE_SIGMA).ceil();
+        Self {
+            std,
+            table: Cdt::new(sigma / (1. + k * k).sqrt()),
+            k: k as i64,
+        }
+    }
+
+    pub fn std(&self) -> f64 {
+        self.std
+    }
+
+    /// Centered sample, in grid steps
+    pub fn sample_integer(&self, state: &mut impl rand::Rng) -> i64 {
+        let x = self.table.sample(state);
+        if self.k == 1 {
+            x
+        } else {
+            x + self.k * self.table.sample(state)
+        }
+    }
+
+    pub fn sample(&self, state: &mut impl rand::Rng) -> Torus {
+        Torus::new(self.sample_integer(state) as TorusRepr)
+    }
+
+    /// Constant-time [`Self::sample_integer`], consuming the same randomness
+    #[cfg(feature = "constant_time")]
+    pub fn sample_integer_ct(&self, state: &mut impl rand::Rng) -> i64 {
+        let x = self.table.sample_ct(state);
+        if self.k == 1 {
+            x
+        } else {
+            x + self.k * self.table.sample_ct(state)
+        }
+    }
+
+    #[cfg(feature = "constant_time")]
+    pub fn sample_ct(&self, state: &mut impl rand::Rng) -> Torus {
+        Torus::new(self.sample_integer_ct(state) as TorusRepr)
+    }
+
+    /// Every coefficient is sampled independently
+    pub fn sample_polynomial<const N: usize>(
+        &self,
+        state: &mut impl rand::Rng,
+    ) -> TorusPolynomial<N> {
+        TorusPolynomial::new(std::array::from_fn(|_| self.sample(state)))
+    }
+}
+
+/// Selects the discrete Gaussian sampler for [`NormalSample`]: the mean is
+/// rounded to the grid, then centered noise from [`DiscreteGaussian`] is added.
+///
+/// The table of the last standard deviation is cached per thread, so
+/// repeated calls with the same std only pay for the sampling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiscreteNormal<T>(pub T);
+
+thread_local! {
+    static CACHE: RefCell<Option<Rc<DiscreteGaussian>>> = const { RefCell::new(None) };
+}
+
+fn cached(std: f64) -> Rc<DiscreteGaussian> {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        match &*cache {
+            Some(sampler) if sampler.std.to_bits() == std.to_bits() => sampler.clone(),
+            _ => {
+                let sampler = Rc::new(DiscreteGaussian::new(std));
+                *cache = Some(sampler.clone());
+                sampler
+            }
+        }
+    })
+}
+
+impl NormalSample for DiscreteNormal<Torus> {
+    type Mean = f64;
+    type Variance = f64;
+
+    fn normal_sample(mean: f64, std: f64, state: &mut impl rand::Rng) -> Self {
+        DiscreteNormal(Torus::from(mean) + cached(std).sample(state))
+    }
+}
+
+impl<const N: usize> NormalSample for DiscreteNormal<TorusPolynomial<N>> {
+    type Mean = f64;
+    type Variance = f64;
+
+    fn normal_sample(mean: f64, std: f64, state: &mut impl rand::Rng) -> Self {
+        let sampler = cached(std);
+        let mean = Torus::from(mean);
+        DiscreteNormal(TorusPolynomial::new(std::array::from_fn(|_| {
+            mean + sampler.sample(state)
+        })))
+    }
+}
+
+/// Centered encryption noise from the constant-time sampler, with the
+/// table of std cached as for [`DiscreteNormal`]
+#[cfg(feature = "constant_time")]
+pub(crate) fn encryption_noise_ct(std: f64, state: &mut impl rand::Rng) -> Torus {
+    cached(std).sample_ct(state)
+}
+
+/// [`encryption_noise_ct`] for every coefficient
+#[cfg(feature = "constant_time")]
+pub(crate) fn encryption_noise_polynomial_ct<const N: usize>(
+    std: f64,
+    state: &mut impl rand::Rng,
+) -> TorusPolynomial<N> {
+    let sampler = cached(std);
+    TorusPolynomial::new(std::array::from_fn(|_| sampler.sample_ct(state)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    const SAMPLES: usize = 200_000;
+
+    fn grid(sigma: f64) -> f64 {
+        sigma / 2f64.powi(32)
+    }
+
+    /// Exact probabilities of D_{Z,σ} summed over each bin [lo, hi), the
+    /// first and last bins extended to infinity
+    fn expected(sigma: f64, edges: &[i64]) -> Vec<f64> {
+        let range = (20. * sigma).ceil() as i64 + 1;
+        let rho = |x: i64| (-((x * x) as f64) / (2. * sigma * sigma)).exp();
+        let total: f64 = (-range..=range).map(rho).sum();
+        let mut out = vec![0.; edges.len() + 1];
+        for x in -range..=range {
+            out[edges.partition_point(|&e| e <= x)] += rho(x) / total;
+        }
+        out
+    }
+
+    /// Pearson statistic of the counts of samples over the bins
+    fn chi_squared(sampler: &DiscreteGaussian, sigma: f64, edges: &[i64]) -> f64 {
+        let mut rng = ChaCha20Rng::seed_from_u64(0x5eed);
+        let mut counts = vec![0usize; edges.len() + 1];
+        for _ in 0..SAMPLES {
+            let x = sampler.sample_integer(&mut rng);
+            counts[edges.partition_point(|&e| e <= x)] += 1;
+        }
+        counts
+            .iter()
+            .zip(expected(sigma, edges))
+            .map(|(&o, p)| {
+                let e = p * SAMPLES as f64;
+                (o as f64 - e).powi(2) / e
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_chi_squared_table() {
+        let sigma = 2.5;
+        let sampler = DiscreteGaussian::new(grid(sigma));
+        assert_eq!(sampler.k, 1);
+        // one bin per integer in [-8, 8], plus the two tails
+        let edges: Vec<i64> = (-8..=9).collect();
+        let chi2 = chi_squared(&sampler, sigma, &edges);
+        // critical value for 18 degrees of freedom at p = 0.001
+        assert!(chi2 < 42.31, "chi2 = {}", chi2);
+    }
+
+    #[test]
+    fn test_chi_squared_convolution() {
+        let sigma = 5000.;
+        let sampler = DiscreteGaussian::new(grid(sigma));
+        assert!(sampler.k > 1);
+        // bins of width σ/4 over [-3σ, 3σ]
+        let edges: Vec<i64> = (-12..=12).map(|i| i * 1250).collect();
+        let chi2 = chi_squared(&sampler, sigma, &edges);
+        // critical value for 25 degrees of freedom at p = 0.001
+        assert!(chi2 < 52.62, "chi2 = {}", chi2);
+    }
+
+    #[test]
+    fn test_chi_squared_detects_wrong_width() {
+        let sampler = DiscreteGaussian::new(grid(2.5));
+        let edges: Vec<i64> = (-8..=9).collect();
+        assert!(chi_squared(&sampler, 2.7, &edges) > 42.31);
+    }
+
+    #[test]
+    fn test_small_std_moments() {
+        // 2^-25, where rounding through f64 loses the low-order bits
+        let sampler = DiscreteGaussian::new(2f64.powi(-25));
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        let samples: Vec<f64> = (0..SAMPLES)
+            .map(|_| sampler.sample_integer(&mut rng) as f64)
+            .collect();
+        let mean = samples.iter().sum::<f64>() / SAMPLES as f64;
+        let variance = samples.iter().map(|x| x * x).sum::<f64>() / SAMPLES as f64;
+        assert!(mean.abs() < 1., "mean = {}", mean);
+        assert_relative_eq!(variance.sqrt(), 128., max_relative = 0.01);
+        assert!(samples.iter().any(|&x| x as i64 % 2 != 0));
+    }
+
+    #[test]
+    fn test_zero_std() {
+        let sampler = DiscreteGaussian::new(0.);
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        assert!((0..100).all(|_| sampler.sample(&mut rng) == Torus::new(0)));
+    }
+
+    #[cfg(feature = "constant_time")]
+    #[test]
+    fn test_constant_time_matches() {
+        for sigma in [0., 0.3, 2.5, 128., 5000.] {
+            let sampler = DiscreteGaussian::new(grid(sigma));
+            let mut rng = ChaCha20Rng::seed_from_u64(4);
+            let mut rng_ct = rng.clone();
+            for _ in 0..2000 {
+                assert_eq!(
+                    sampler.sample_integer(&mut rng),
+                    sampler.sample_integer_ct(&mut rng_ct)
+                );
+            }
+            assert_eq!(sampler.sample(&mut rng), sampler.sample_ct(&mut rng_ct));
+        }
+    }
+
+    #[test]
+    fn test_normal_sample_strategy() {
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        let std = grid(3.);
+        for _ in 0..100 {
+            let DiscreteNormal(t) = DiscreteNormal::<Torus>::normal_sample(0.25, std, &mut rng);
+            assert!(Torus::distance(t, Torus::from(0.25)).abs() <= 39);
+        }
+        let DiscreteNormal(p) =
+            DiscreteNormal::<TorusPolynomial<16>>::normal_sample(0., std, &mut rng);
+        assert!(p.coefs.iter().all(|c| c.abs_distance_to_zero() <= 39));
+        assert!(!p.coefs.iter().all(|c| c.inner() == 0));
+
+        // the cache follows the requested std
+        let DiscreteNormal(wide) = DiscreteNormal::<Torus>::normal_sample(0., 1e-3, &mut rng);
+        let DiscreteNormal(narrow) = DiscreteNormal::<Torus>::normal_sample(0., 0., &mut rng);
+        assert_eq!(narrow, Torus::new(0));
+        assert!(wide.abs_distance_to_zero() < 1 << 26);
+    }
+}
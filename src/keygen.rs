@@ -0,0 +1,182 @@
+//! Distributions of secret key coefficients.
+//!
+//! A [`KeyDistribution`] samples the integer coefficients behind
+//! [`LweSecretKey`](crate::lwe::LweSecretKey) and
+//! [`TrlweSecretKey`](crate::trlwe::TrlweSecretKey), see their
+//! `generate_with` and `from_seed`. Blind rotation needs binary LWE keys;
+//! encryption, decryption and key switching work with any of them.
+
+use crate::gaussian::DiscreteGaussian;
+use crate::lwe::Seed;
+use crate::rng::TorusRng;
+
+pub trait KeyDistribution {
+    /// len independent coefficients, unless the distribution says otherwise
+    fn sample_coefs(&self, len: usize, state: &mut impl rand::Rng) -> Vec<i32>;
+
+    /// Same coefficients for the same seed, from a [`TorusRng`] stream
+    fn sample_coefs_seeded(&self, len: usize, seed: Seed) -> Vec<i32> {
+        self.sample_coefs(len, &mut TorusRng::from_seed(seed))
+    }
+}
+
+/// Uniform in {0, 1}
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BinaryKey;
+
+impl KeyDistribution for BinaryKey {
+    fn sample_coefs(&self, len: usize, state: &mut impl rand::Rng) -> Vec<i32> {
+        (0..len).map(|_| i32::from(state.gen::<bool>())).collect()
+    }
+}
+
+/// Exactly hamming_weight coefficients in {-1, 1} at uniform positions, the
+/// others 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TernaryKey {
+    pub hamming_weight: usize,
+}
+
+impl KeyDistribution for TernaryKey {
+    fn sample_coefs(&self, len: usize, state: &mut impl rand::Rng) -> Vec<i32> {
+        assert!(
+            self.hamming_weight <= len,
+            "hamming weight larger than the dimension"
+        );
+        let mut coefs = vec![0; len];
+        for i in rand::seq::index::sample(state, len, self.hamming_weight) {
+            coefs[i] = if state.gen::<bool>() { -1 } else { 1 };
+        }
+        coefs
+    }
+}
+
+/// Discrete Gaussian D_{Z,std}, std in integer units
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GaussianKey {
+    pub std: f64,
+}
+
+impl KeyDistribution for GaussianKey {
+    fn sample_coefs(&self, len: usize, state: &mut impl rand::Rng) -> Vec<i32> {
+        // DiscreteGaussian counts in steps of 2^-32
+        let sampler = DiscreteGaussian::new(self.std / 2f64.powi(32));
+        (0..len)
+            .map(|_| {
+                i32::try_from(sampler.sample_integer(state)).expect("coefficient out of range")
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    const LEN: usize = 1024;
+
+    #[test]
+    fn test_binary() {
+        let mut rng = ChaCha20Rng::seed_from_u64(8);
+        let coefs = BinaryKey.sample_coefs(100 * LEN, &mut rng);
+        assert!(coefs.iter().all(|&c| c == 0 || c == 1));
+        // 5 standard deviations of the binomial
+        let ones = coefs.iter().filter(|&&c| c == 1).count() as f64;
+        assert!((ones - 50. * LEN as f64).abs() < 5. * (25. * LEN as f64).sqrt());
+    }
+
+    #[test]
+    fn test_ternary_hamming_weight() {
+        let mut rng = ChaCha20Rng::seed_from_u64(9);
+        for hamming_weight in [0, 1, 63, 512, LEN] {
+            let coefs = TernaryKey { hamming_weight }.sample_coefs(LEN, &mut rng);
+            assert_eq!(coefs.len(), LEN);
+            assert!(coefs.iter().all(|&c| (-1..=1).contains(&c)));
+            assert_eq!(coefs.iter().filter(|&&c| c != 0).count(), hamming_weight);
+        }
+    }
+
+    #[test]
+    fn test_ternary_distribution() {
+        let mut rng = ChaCha20Rng::seed_from_u64(10);
+        let key = TernaryKey { hamming_weight: 64 };
+        let mut positions = [0usize; 16];
+        let mut minus = 0;
+        const ROUNDS: usize = 2000;
+        for _ in 0..ROUNDS {
+            for (i, &c) in key.sample_coefs(16 * 64, &mut rng).iter().enumerate() {
+                if c != 0 {
+                    positions[i / 64] += 1;
+                }
+                if c == -1 {
+                    minus += 1;
+                }
+            }
+        }
+        // each block of 64 positions holds 1/16 of the weight
+        let expected = (ROUNDS * 64 / 16) as f64;
+        let chi2: f64 = positions
+            .iter()
+            .map(|&c| (c as f64 - expected).powi(2) / expected)
+            .sum();
+        // critical value for 15 degrees of freedom at p = 0.001
+        assert!(chi2 < 37.70, "chi2 = {}", chi2);
+        let total = (ROUNDS * 64) as f64;
+        assert!((minus as f64 - total / 2.).abs() < 5. * (total / 4.).sqrt());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ternary_too_heavy() {
+        let mut rng = ChaCha20Rng::seed_from_u64(11);
+        TernaryKey { hamming_weight: 5 }.sample_coefs(4, &mut rng);
+    }
+
+    #[test]
+    fn test_gaussian() {
+        let mut rng = ChaCha20Rng::seed_from_u64(12);
+        let std = 3.2;
+        let coefs = GaussianKey { std }.sample_coefs(100 * LEN, &mut rng);
+        let n = coefs.len() as f64;
+        let mean = coefs.iter().map(|&c| c as f64).sum::<f64>() / n;
+        let variance = coefs.iter().map(|&c| (c as f64).powi(2)).sum::<f64>() / n;
+        assert!(mean.abs() < 0.1, "mean = {}", mean);
+        assert_relative_eq!(variance.sqrt(), std, max_relative = 0.02);
+        // tail cut at 13 std
+        assert!(coefs.iter().all(|&c| c.abs() <= 42));
+    }
+
+    #[test]
+    fn test_seeded() {
+        let (a, b) = ([7; 32], [8; 32]);
+        let binary = BinaryKey;
+        assert_eq!(
+            binary.sample_coefs_seeded(LEN, a),
+            binary.sample_coefs_seeded(LEN, a)
+        );
+        assert_ne!(
+            binary.sample_coefs_seeded(LEN, a),
+            binary.sample_coefs_seeded(LEN, b)
+        );
+        let ternary = TernaryKey { hamming_weight: 64 };
+        assert_eq!(
+            ternary.sample_coefs_seeded(LEN, a),
+            ternary.sample_coefs_seeded(LEN, a)
+        );
+        assert_ne!(
+            ternary.sample_coefs_seeded(LEN, a),
+            ternary.sample_coefs_seeded(LEN, b)
+        );
+        let gaussian = GaussianKey { std: 3.2 };
+        assert_eq!(
+            gaussian.sample_coefs_seeded(LEN, a),
+            gaussian.sample_coefs_seeded(LEN, a)
+        );
+        assert_ne!(
+            gaussian.sample_coefs_seeded(LEN, a),
+            gaussian.sample_coefs_seeded(LEN, b)
+        );
+    }
+}
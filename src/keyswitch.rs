@@ -0,0 +1,270 @@
+use crate::decomposition::GadgetDecomposer;
+use crate::lwe::LweCiphertext;
+#[cfg(feature = "random")]
+use crate::lwe::LweSecretKey;
+use crate::slice::scalar_mul_add;
+use crate::wire::{Kind, WireError, WireFormat, WireReader, WireWriter};
+#[cfg(feature = "random")]
+use crate::Torus;
+use std::io::{Read, Write};
+
+/// LWE encryptions under the destination key of s_i * Bks^-(j+1)
+/// for every coefficient s_i of the source key and every level j.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "KeySwitchingKeyRaw"))]
+pub struct KeySwitchingKey {
+    /// keys[i * level + j]
+    keys: Vec<LweCiphertext>,
+    decomposer: GadgetDecomposer,
+    output_dimension: usize,
+}
+
+/// Unchecked fields of a deserialized [`KeySwitchingKey`]
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct KeySwitchingKeyRaw {
+    keys: Vec<LweCiphertext>,
+    decomposer: GadgetDecomposer,
+    output_dimension: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<KeySwitchingKeyRaw> for KeySwitchingKey {
+    type Error = &'static str;
+
+    fn try_from(raw: KeySwitchingKeyRaw) -> Result<Self, Self::Error> {
+        if !raw.keys.len().is_multiple_of(raw.decomposer.level) {
+            return Err("key count is not a multiple of the level");
+        }
+        if raw
+            .keys
+            .iter()
+            .any(|key| key.dimension() != raw.output_dimension)
+        {
+            return Err("key dimension mismatch");
+        }
+        Ok(Self {
+            keys: raw.keys,
+            decomposer: raw.decomposer,
+            output_dimension: raw.output_dimension,
+        })
+    }
+}
+
+impl KeySwitchingKey {
+    #[cfg(feature = "random")]
+    pub fn generate(
+        source: &LweSecretKey,
+        destination: &LweSecretKey,
+        decomposer: GadgetDecomposer,
+        noise_std: f64,
+        state: &mut impl rand::Rng,
+    ) -> Self {
+        Self::from_gadgets(source, destination, decomposer, |m| {
+            destination.encrypt(m, noise_std, state)
+        })
+    }
+
+    /// [`Self::generate`] with the masks and the noise drawn from separate
+    /// generators, see [`LweSecretKey::encrypt_with_streams`]
+    #[cfg(feature = "random")]
+    pub fn generate_with_streams(
+        source: &LweSecretKey,
+        destination: &LweSecretKey,
+        decomposer: GadgetDecomposer,
+        noise_std: f64,
+        mask_state: &mut impl rand::Rng,
+        noise_state: &mut impl rand::Rng,
+    ) -> Self {
+        Self::from_gadgets(source, destination, decomposer, |m| {
+            destination.encrypt_with_streams(m, noise_std, mask_state, noise_state)
+        })
+    }
+
+    /// encrypt(s_i * Bks^-(j+1)) for every i and j
+    #[cfg(feature = "random")]
+    fn from_gadgets(
+        source: &LweSecretKey,
+        destination: &LweSecretKey,
+        decomposer: GadgetDecomposer,
+        encrypt: impl FnMut(Torus) -> LweCiphertext,
+    ) -> Self {
+        let keys = source
+            .coefs()
+            .iter()
+            .flat_map(|&s| (0..decomposer.level).map(move |j| decomposer.gadget(j) * s))
+            .map(encrypt)
+            .collect();
+        Self {
+            keys,
+            decomposer,
+            output_dimension: destination.dimension(),
+        }
+    }
+
+    pub fn input_dimension(&self) -> usize {
+        self.keys.len() / self.decomposer.level
+    }
+
+    pub fn output_dimension(&self) -> usize {
+        self.output_dimension
+    }
+
+    pub fn decomposer(&self) -> GadgetDecomposer {
+        self.decomposer
+    }
+
+    /// Same phase, under the destination key
+    pub fn keyswitch(&self, ct: &LweCiphertext) -> LweCiphertext {
+        assert_eq!(ct.dimension(), self.input_dimension(), "dimension mismatch");
+
+        let level = self.decomposer.level;
+        let mut out = LweCiphertext::trivial(ct.body, self.output_dimension);
+        for (i, &a) in ct.mask.iter().enumerate() {
+            for (j, digit) in self.decomposer.decompose(a).into_iter().enumerate() {
+                if digit != 0 {
+                    let key = &self.keys[i * level + j];
+                    scalar_mul_add(&mut out.mask, &key.mask, -digit);
+                    out.body -= key.body * digit;
+                }
+            }
+        }
+        out
+    }
+}
+
+impl WireFormat for KeySwitchingKey {
+    fn write_to(&self, writer: impl Write) -> Result<(), WireError> {
+        let mut w = WireWriter::new(writer, Kind::KeySwitchingKey)?;
+        w.u64(self.input_dimension() as u64)?;
+        w.u64(self.output_dimension as u64)?;
+        w.decomposer(self.decomposer)?;
+        for key in &self.keys {
+            key.write_payload(&mut w)?;
+        }
+        Ok(())
+    }
+
+    fn read_from(reader: impl Read) -> Result<Self, WireError> {
+        let mut r = WireReader::new(reader, Kind::KeySwitchingKey)?;
+        let input_dimension = r.len()?;
+        let output_dimension = r.len()?;
+        let decomposer = r.decomposer()?;
+        let count = input_dimension
+            .checked_mul(decomposer.level)
+            .ok_or(WireError::InvalidParameters("too many samples"))?;
+        let mut keys = Vec::new();
+        for _ in 0..count {
+            keys.push(LweCiphertext::read_payload(&mut r, output_dimension)?);
+        }
+        Ok(Self {
+            keys,
+            decomposer,
+            output_dimension,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "random"))]
+mod tests {
+    use super::*;
+    use crate::rng::TorusRng;
+
+    const SOURCE_DIMENSION: usize = 512;
+    const DESTINATION_DIMENSION: usize = 128;
+    const BASE_LOG: u32 = 2;
+    const LEVEL: usize = 8;
+    const INPUT_STD: f64 = 1e-6;
+    const KS_STD: f64 = 1e-6;
+
+    /// Var(ks(c)) <= Var(c) + n l (Bks/2)^2 σ_ks^2 + n ε^2, ε = 2^-(βl+1)
+    fn noise_bound() -> f64 {
+        let n = SOURCE_DIMENSION as f64;
+        let half_base = (1u64 << (BASE_LOG - 1)) as f64;
+        let epsilon = 0.5 / (1u64 << (BASE_LOG * LEVEL as u32)) as f64;
+        INPUT_STD.powi(2)
+            + n * LEVEL as f64 * half_base.powi(2) * KS_STD.powi(2)
+            + n * epsilon.powi(2)
+    }
+
+    #[test]
+    fn test_keyswitch_noise() {
+        let mut rng = TorusRng::from_seed([1; 32]);
+        let source = LweSecretKey::generate(SOURCE_DIMENSION, &mut rng);
+        let destination = LweSecretKey::generate(DESTINATION_DIMENSION, &mut rng);
+        let ksk = KeySwitchingKey::generate(
+            &source,
+            &destination,
+            GadgetDecomposer::new(BASE_LOG, LEVEL),
+            KS_STD,
+            &mut rng,
+        );
+        assert_eq!(ksk.input_dimension(), SOURCE_DIMENSION);
+        assert_eq!(ksk.output_dimension(), DESTINATION_DIMENSION);
+
+        let samples = 100;
+        let mut variance = 0.;
+        for i in 0..samples {
+            let m = Torus::new((i % 8) << 29);
+            let ct = source.encrypt(m, INPUT_STD, &mut rng);
+            let switched = ksk.keyswitch(&ct);
+            assert_eq!(switched.dimension(), DESTINATION_DIMENSION);
+
+            let error = (destination.decrypt_phase(&switched) - m).to_centered_f64();
+            assert!(error.abs() < 1. / 16.);
+            variance += error.powi(2) / samples as f64;
+        }
+
+        let bound = noise_bound();
+        assert!(variance <= bound, "{} > {}", variance, bound);
+    }
+
+    #[test]
+    fn test_separate_streams() {
+        let mut rng = TorusRng::from_seed([3; 32]);
+        let source = LweSecretKey::generate(16, &mut rng);
+        let destination = LweSecretKey::generate(DESTINATION_DIMENSION, &mut rng);
+        let generate = |noise_label| {
+            KeySwitchingKey::generate_with_streams(
+                &source,
+                &destination,
+                GadgetDecomposer::new(BASE_LOG, LEVEL),
+                KS_STD,
+                &mut rng.fork("mask"),
+                &mut rng.fork(noise_label),
+            )
+        };
+        let (a, b) = (generate("noise"), generate("other noise"));
+        // another noise stream leaves every mask unchanged
+        for (x, y) in a.keys.iter().zip(&b.keys) {
+            assert_eq!(x.mask, y.mask);
+        }
+        assert_ne!(a, b);
+        assert_eq!(a, generate("noise"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_invalid() {
+        use serde_json::{from_str, from_value, json, to_value};
+
+        // input_dimension * level keys of dimension output_dimension
+        let mut rng = TorusRng::from_seed([2; 32]);
+        let source = LweSecretKey::generate(3, &mut rng);
+        let destination = LweSecretKey::generate(4, &mut rng);
+        let decomposer = GadgetDecomposer::new(BASE_LOG, LEVEL);
+        let ksk = KeySwitchingKey::generate(&source, &destination, decomposer, KS_STD, &mut rng);
+        let valid = to_value(&ksk).unwrap();
+        assert_eq!(from_value::<KeySwitchingKey>(valid.clone()).unwrap(), ksk);
+
+        let probe = r#"{"keys":[],"decomposer":{"base_log":2,"level":0},"output_dimension":4}"#;
+        assert!(from_str::<KeySwitchingKey>(probe).is_err());
+        let mut bad = valid.clone();
+        bad["keys"].as_array_mut().unwrap().pop();
+        assert!(from_value::<KeySwitchingKey>(bad).is_err());
+        let mut bad = valid;
+        bad["output_dimension"] = json!(5);
+        assert!(from_value::<KeySwitchingKey>(bad).is_err());
+    }
+}
@@ -2,188 +2,577 @@
 #[macro_use]
 extern crate approx;
 
-type TorusRepr = u32;
-
-/// Fixed point float
-/// for example, 0b10000000... = 0.5
-/// So, for all t in Torus, 0 <= t < 1
-#[derive(Clone, Copy)]
-pub struct Torus {
-    inner: TorusRepr,
+pub mod bootstrap;
+pub mod decomposition;
+pub mod encoding;
+pub mod fft;
+pub mod gates;
+#[cfg(feature = "random")]
+pub mod gaussian;
+#[cfg(feature = "random")]
+pub mod keygen;
+pub mod keyswitch;
+pub mod lwe;
+pub mod ntt;
+pub mod polynomial;
+#[cfg(feature = "random")]
+pub mod rng;
+pub mod slice;
+pub mod trgsw;
+pub mod trlwe;
+pub mod wire;
+
+/// How a real number is rounded to the torus grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rounding {
+    /// Nearest grid point, ties to even
+    NearestEven,
+    TowardZero,
+    Floor,
 }
 
-impl Torus {
-    const SHIFT: u32 = TorusRepr::MAX;
+/// Common interface of the fixed point torus types of every width.
+pub trait TorusScalar:
+    Copy
+    + std::fmt::Debug
+    + std::ops::Add<Output = Self>
+    + std::ops::Sub<Output = Self>
+    + std::ops::Neg<Output = Self>
+    + std::ops::Mul<i32, Output = Self>
+    + std::ops::Mul<f64, Output = Self>
+    + std::ops::AddAssign
+    + std::ops::SubAssign
+    + From<f64>
+    + Into<f64>
+    + num_traits::identities::Zero
+    + num_traits::identities::ConstZero
+{
+    type Repr: Copy;
+
+    /// Number of bits of the backing integer.
+    const BITS: u32;
+
+    fn new(inner: Self::Repr) -> Self;
+    fn inner(&self) -> Self::Repr;
+    fn sign(&self) -> i32;
+}
 
-    pub fn new(inner: TorusRepr) -> Torus {
-        Torus { inner }
-    }
+macro_rules! impl_torus {
+    ($name:ident, $repr:ty, $signed:ty) => {
+        /// Fixed point float
+        /// for example, 0b10000000... = 0.5
+        /// So, for all t in Torus, 0 <= t < 1
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        #[repr(transparent)]
+        pub struct $name {
+            inner: $repr,
+        }
 
-    pub fn sign(&self) -> i32 {
-        if self.inner < (Torus::SHIFT / 2) {
-            1
-        } else {
-            -1
+        impl $name {
+            pub const fn new(inner: $repr) -> $name {
+                $name { inner }
+            }
+
+            pub const fn inner(&self) -> $repr {
+                self.inner
+            }
+
+            /// 2^BITS as f64, exact
+            fn scale() -> f64 {
+                2f64.powi(<$repr>::BITS as i32)
+            }
+
+            /// f mod 1, rounded to a multiple of 2^-BITS
+            pub fn from_f64(f: f64, rounding: Rounding) -> $name {
+                let scaled = f * $name::scale();
+                let rounded = match rounding {
+                    Rounding::NearestEven => scaled.round_ties_even(),
+                    Rounding::TowardZero => scaled.trunc(),
+                    Rounding::Floor => scaled.floor(),
+                };
+                // exact, |reduced| < 2^BITS
+                let reduced = rounded % $name::scale();
+                let inner = if reduced >= 0. {
+                    reduced as $repr
+                } else {
+                    // reduced + 2^BITS may not be representable
+                    ((-reduced) as $repr).wrapping_neg()
+                };
+                $name { inner }
+            }
+
+            /// inner / 2^BITS, exact as long as inner fits in the f64 mantissa
+            pub fn to_f64(&self) -> f64 {
+                self.inner as f64 / $name::scale()
+            }
+
+            /// num / den mod 1, rounded to the nearest grid point (ties up)
+            pub fn from_fraction(num: i64, den: u64) -> $name {
+                assert!(den > 0, "denominator must be positive");
+                let den = den as u128;
+                let mut rest = (num as i128).rem_euclid(den as i128) as u128;
+
+                // long division of rest * 2^BITS by den
+                let mut inner: $repr = 0;
+                for _ in 0..<$repr>::BITS {
+                    rest <<= 1;
+                    inner <<= 1;
+                    if rest >= den {
+                        rest -= den;
+                        inner |= 1;
+                    }
+                }
+                if 2 * rest >= den {
+                    inner = inner.wrapping_add(1);
+                }
+                $name { inner }
+            }
+
+            /// k / 2^log2_den mod 1, exact when log2_den <= BITS and rounded
+            /// to the nearest grid point (ties up) otherwise
+            pub fn from_bits_fraction(k: i64, log2_den: u32) -> $name {
+                assert!(
+                    log2_den <= <$repr>::BITS.max(127),
+                    "denominator too large"
+                );
+                let bits = <$repr>::BITS;
+                let inner = if log2_den == 0 {
+                    0
+                } else if log2_den <= bits {
+                    // sign extension (or truncation) keeps k mod 2^BITS
+                    (k as $repr) << (bits - log2_den)
+                } else {
+                    let shift = log2_den - bits;
+                    ((k as i128 + (1 << (shift - 1))) >> shift) as $repr
+                };
+                $name { inner }
+            }
+
+            /// Sum and whether it wrapped past 1 (back to 0)
+            pub fn overflowing_add(self, rhs: $name) -> ($name, bool) {
+                let (inner, overflow) = self.inner.overflowing_add(rhs.inner);
+                ($name { inner }, overflow)
+            }
+
+            /// Difference and whether it wrapped below 0
+            pub fn overflowing_sub(self, rhs: $name) -> ($name, bool) {
+                let (inner, overflow) = self.inner.overflowing_sub(rhs.inner);
+                ($name { inner }, overflow)
+            }
+
+            /// Product and whether the exact product left [0, 1)
+            pub fn overflowing_mul_int(self, rhs: i32) -> ($name, bool) {
+                // exact product in u128, rhs may be wider than $repr
+                let inner = (self.inner as u128).wrapping_mul(rhs as i128 as u128) as $repr;
+                let overflow = if rhs >= 0 {
+                    !matches!(
+                        (self.inner as u128).checked_mul(rhs as u128),
+                        Some(product) if product <= <$repr>::MAX as u128
+                    )
+                } else {
+                    self.inner != 0
+                };
+                ($name { inner }, overflow)
+            }
+
+            pub fn checked_add(self, rhs: $name) -> Option<$name> {
+                match self.overflowing_add(rhs) {
+                    (t, false) => Some(t),
+                    (_, true) => None,
+                }
+            }
+
+            pub fn checked_sub(self, rhs: $name) -> Option<$name> {
+                match self.overflowing_sub(rhs) {
+                    (t, false) => Some(t),
+                    (_, true) => None,
+                }
+            }
+
+            pub fn checked_mul_int(self, rhs: i32) -> Option<$name> {
+                match self.overflowing_mul_int(rhs) {
+                    (t, false) => Some(t),
+                    (_, true) => None,
+                }
+            }
+
+            /// Clamped to [0, 1 - 2^-BITS] instead of wrapping
+            pub fn saturating_add(self, rhs: $name) -> $name {
+                $name::new(self.inner.saturating_add(rhs.inner))
+            }
+
+            /// Clamped to [0, 1 - 2^-BITS] instead of wrapping
+            pub fn saturating_sub(self, rhs: $name) -> $name {
+                $name::new(self.inner.saturating_sub(rhs.inner))
+            }
+
+            /// Whether self + rhs, both seen as centered values in [-1/2, 1/2),
+            /// leaves that range, i.e. an accumulated error crosses 1/2 and
+            /// flips to the opposite side of the circle.
+            pub fn wraps_past_half(self, rhs: $name) -> bool {
+                (self.inner as $signed)
+                    .overflowing_add(rhs.inner as $signed)
+                    .1
+            }
+
+            /// Shortest signed distance a - b on the circle, in [-1/2, 1/2),
+            /// in units of 2^-BITS
+            pub fn distance(a: $name, b: $name) -> $signed {
+                (a - b).to_signed()
+            }
+
+            /// round(t * 2^log_modulus) mod 2^log_modulus, ties up
+            pub fn mod_switch(&self, log_modulus: u32) -> $repr {
+                assert!(
+                    log_modulus <= <$repr>::BITS,
+                    "modulus exceeds torus precision"
+                );
+                if log_modulus == 0 {
+                    return 0;
+                }
+                if log_modulus == <$repr>::BITS {
+                    return self.inner;
+                }
+                let shift = <$repr>::BITS - log_modulus;
+                let rounded = (self.inner >> shift) + ((self.inner >> (shift - 1)) & 1);
+                // rounding up from just below 1 gives 2^log_modulus, i.e. 0
+                rounded & ((1 << log_modulus) - 1)
+            }
+
+            /// Nearest multiple of 2^-bits, ties up.
+            /// Panics if bits > BITS, as [`Self::mod_switch`] does.
+            pub fn round_to_bits(&self, bits: u32) -> $name {
+                let switched = self.mod_switch(bits);
+                $name::new(switched.checked_shl(<$repr>::BITS - bits).unwrap_or(0))
+            }
+
+            pub fn mod_switch_slice(ts: &[$name], log_modulus: u32) -> Vec<$repr> {
+                ts.iter().map(|t| t.mod_switch(log_modulus)).collect()
+            }
+
+            pub fn round_slice_to_bits(ts: &[$name], bits: u32) -> Vec<$name> {
+                ts.iter().map(|t| t.round_to_bits(bits)).collect()
+            }
+
+            /// Two's-complement view of the inner value: t in [1/2, 1) maps to
+            /// t - 1, so the result is in [-2^(BITS-1), 2^(BITS-1))
+            pub const fn to_signed(&self) -> $signed {
+                self.inner as $signed
+            }
+
+            /// Inverse of [`Self::to_signed`]
+            pub const fn from_signed(x: $signed) -> $name {
+                $name { inner: x as $repr }
+            }
+
+            /// Centered value in [-0.5, 0.5)
+            pub fn to_centered_f64(&self) -> f64 {
+                self.to_signed() as f64 / $name::scale()
+            }
+
+            /// |t| in the centered view, in units of 2^-BITS
+            pub const fn abs_distance_to_zero(&self) -> $repr {
+                self.to_signed().unsigned_abs()
+            }
+
+            /// 1 on [0, 1/2), -1 on [1/2, 1)
+            pub fn sign(&self) -> i32 {
+                if self.to_signed() >= 0 {
+                    1
+                } else {
+                    -1
+                }
+            }
         }
-    }
-}
 
-#[cfg(feature = "random")]
-impl distr_traits::uniform::UniformSample for Torus {
-    fn uniform_sample(state: &mut impl rand::Rng) -> Self {
-        use rand::distributions::Distribution;
+        impl TorusScalar for $name {
+            type Repr = $repr;
 
-        let uniform = rand::distributions::Uniform::new(0., 1.);
-        let sample = uniform.sample(state);
+            const BITS: u32 = <$repr>::BITS;
 
-        Torus::from(sample)
-    }
-}
+            fn new(inner: $repr) -> $name {
+                $name::new(inner)
+            }
 
-#[cfg(feature = "random")]
-impl distr_traits::normal::NormalSample for Torus {
-    type Mean = f64;
-    type Variance = f64;
+            fn inner(&self) -> $repr {
+                self.inner
+            }
 
-    fn normal_sample(mean: f64, std: f64, state: &mut impl rand::Rng) -> Self {
-        use rand::distributions::Distribution;
+            fn sign(&self) -> i32 {
+                $name::sign(self)
+            }
+        }
 
-        let normal = statrs::distribution::Normal::new(mean, std).unwrap();
-        let sample = normal.sample(state);
+        #[cfg(feature = "random")]
+        impl distr_traits::uniform::UniformSample for $name {
+            /// Raw draw of the repr, so every grid point is equally likely
+            fn uniform_sample(state: &mut impl rand::Rng) -> Self {
+                $name::new(state.gen())
+            }
+        }
 
-        Torus::from(sample)
-    }
-}
+        #[cfg(feature = "random")]
+        impl distr_traits::normal::NormalSample for $name {
+            type Mean = f64;
+            type Variance = f64;
 
-impl num_traits::identities::ConstZero for Torus {
-    const ZERO: Self = Torus { inner: 0 };
-}
+            fn normal_sample(mean: f64, std: f64, state: &mut impl rand::Rng) -> Self {
+                use rand::distributions::Distribution;
 
-impl num_traits::identities::Zero for Torus {
-    fn zero() -> Self {
-        Self { inner: 0 }
-    }
+                let normal = statrs::distribution::Normal::new(mean, std).unwrap();
+                let sample = normal.sample(state);
 
-    fn is_zero(&self) -> bool {
-        self.inner == 0
-    }
-}
+                $name::from(sample)
+            }
+        }
 
-impl From<f64> for Torus {
-    fn from(f: f64) -> Torus {
-        let f = f.rem_euclid(1.0);
-        let inner = (f * (Torus::SHIFT as f64)) as TorusRepr;
-        Torus { inner }
-    }
-}
+        impl num_traits::identities::ConstZero for $name {
+            const ZERO: Self = $name { inner: 0 };
+        }
 
-impl From<Torus> for f64 {
-    fn from(t: Torus) -> f64 {
-        // TODO: overflow?
-        (t.inner as f64) / (Torus::SHIFT as f64)
-    }
-}
+        impl num_traits::identities::Zero for $name {
+            fn zero() -> Self {
+                Self { inner: 0 }
+            }
 
+            fn is_zero(&self) -> bool {
+                self.inner == 0
+            }
+        }
 
-impl std::fmt::Debug for Torus {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Torus({})", f64::from(*self))
-    }
-}
+        /// Rounds to the nearest grid point, ties to even
+        impl From<f64> for $name {
+            fn from(f: f64) -> $name {
+                $name::from_f64(f, Rounding::NearestEven)
+            }
+        }
 
-impl std::fmt::Display for Torus {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", f64::from(*self))
-    }
-}
+        impl From<$name> for f64 {
+            fn from(t: $name) -> f64 {
+                t.to_f64()
+            }
+        }
 
-impl std::ops::Add for Torus {
-    type Output = Torus;
+        /// The raw inner value in compact formats. Human-readable formats use
+        /// the float value when it is exact (up to 53 bits), and the raw
+        /// value otherwise.
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if serializer.is_human_readable() && <$repr>::BITS <= f64::MANTISSA_DIGITS {
+                    serializer.serialize_f64(self.to_f64())
+                } else {
+                    serde::Serialize::serialize(&self.inner, serializer)
+                }
+            }
+        }
 
-    fn add(self, other: Torus) -> Torus {
-        let inner = self.inner.wrapping_add(other.inner);
-        Torus { inner }
-    }
-}
+        /// Human-readable formats accept both a float in [0, 1), rounded to
+        /// nearest, and the raw inner value
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                use serde::de::{Error, Unexpected};
+
+                struct Visitor;
+
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        write!(f, "a float in [0, 1) or a raw {}", stringify!($repr))
+                    }
+
+                    fn visit_u64<E: Error>(self, v: u64) -> Result<$name, E> {
+                        <$repr>::try_from(v)
+                            .map($name::new)
+                            .map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &self))
+                    }
+
+                    fn visit_f64<E: Error>(self, v: f64) -> Result<$name, E> {
+                        if (0. ..1.).contains(&v) {
+                            Ok($name::from(v))
+                        } else {
+                            Err(E::invalid_value(Unexpected::Float(v), &self))
+                        }
+                    }
+                }
+
+                if deserializer.is_human_readable() && <$repr>::BITS <= u64::BITS {
+                    deserializer.deserialize_any(Visitor)
+                } else {
+                    <$repr as serde::Deserialize>::deserialize(deserializer).map($name::new)
+                }
+            }
+        }
 
-impl std::ops::Sub for Torus {
-    type Output = Torus;
+        impl std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}({})", stringify!($name), f64::from(*self))
+            }
+        }
 
-    fn sub(self, other: Torus) -> Torus {
-        let inner = self.inner.wrapping_sub(other.inner);
-        Torus { inner }
-    }
-}
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}", f64::from(*self))
+            }
+        }
 
-impl std::ops::AddAssign for Torus {
-    fn add_assign(&mut self, other: Torus) {
-        *self = *self + other;
-    }
-}
+        impl std::ops::Add for $name {
+            type Output = $name;
 
-impl std::ops::SubAssign for Torus {
-    fn sub_assign(&mut self, other: Torus) {
-        *self = *self - other;
-    }
-}
+            fn add(self, other: $name) -> $name {
+                let inner = self.inner.wrapping_add(other.inner);
+                $name { inner }
+            }
+        }
 
-impl std::ops::Neg for Torus {
-    type Output = Torus;
+        impl std::ops::Sub for $name {
+            type Output = $name;
 
-    fn neg(self) -> Torus {
-        Torus::new(self.inner.wrapping_neg())
-    }
-}
+            fn sub(self, other: $name) -> $name {
+                let inner = self.inner.wrapping_sub(other.inner);
+                $name { inner }
+            }
+        }
 
-impl std::ops::Mul<i32> for Torus {
-    type Output = Torus;
+        impl std::ops::AddAssign for $name {
+            fn add_assign(&mut self, other: $name) {
+                *self = *self + other;
+            }
+        }
 
-    fn mul(self, rhs: i32) -> Torus {
-        let inner = self.inner.wrapping_mul(rhs as TorusRepr);
-        Torus { inner }
-    }
-}
+        impl std::ops::SubAssign for $name {
+            fn sub_assign(&mut self, other: $name) {
+                *self = *self - other;
+            }
+        }
 
-impl std::ops::MulAssign<i32> for Torus {
-    fn mul_assign(&mut self, rhs: i32) {
-        *self = *self * rhs;
-    }
-}
+        impl std::ops::Neg for $name {
+            type Output = $name;
 
-impl std::ops::Mul<Torus> for i32 {
-    type Output = Torus;
+            fn neg(self) -> $name {
+                $name::new(self.inner.wrapping_neg())
+            }
+        }
 
-    fn mul(self, rhs: Torus) -> Torus {
-        rhs * self
-    }
-}
+        // also gives subtle's blanket ConditionallyNegatable
+        impl std::ops::Neg for &$name {
+            type Output = $name;
 
-impl std::ops::Mul<f64> for Torus {
-    type Output = Torus;
+            fn neg(self) -> $name {
+                -*self
+            }
+        }
 
-    fn mul(self, rhs: f64) -> Torus {
-        let v = f64::from(self) * rhs;
-        Torus::from(v)
-    }
-}
+        #[cfg(feature = "constant_time")]
+        impl $name {
+            /// Branch-free t in [1/2, 1)
+            pub fn ct_is_negative(&self) -> subtle::Choice {
+                subtle::Choice::from((self.inner >> ($name::BITS - 1)) as u8)
+            }
+
+            /// Branch-free [`Self::sign`]
+            pub fn ct_sign(&self) -> i32 {
+                1 - 2 * i32::from(self.ct_is_negative().unwrap_u8())
+            }
+
+            /// Maps the centered order to the order of the repr
+            fn ct_key(&self) -> $repr {
+                self.inner ^ (1 << ($name::BITS - 1))
+            }
+        }
 
-impl std::ops::Mul<Torus> for f64 {
-    type Output = Torus;
+        #[cfg(feature = "constant_time")]
+        impl subtle::ConditionallySelectable for $name {
+            fn conditional_select(a: &Self, b: &Self, choice: subtle::Choice) -> Self {
+                $name::new(<$repr>::conditional_select(&a.inner, &b.inner, choice))
+            }
+        }
 
-    fn mul(self, rhs: Torus) -> Torus {
-        rhs * self
-    }
-}
+        #[cfg(feature = "constant_time")]
+        impl subtle::ConstantTimeEq for $name {
+            fn ct_eq(&self, other: &Self) -> subtle::Choice {
+                self.inner.ct_eq(&other.inner)
+            }
+        }
 
-impl std::ops::MulAssign<f64> for Torus {
-    fn mul_assign(&mut self, rhs: f64) {
-        *self = *self * rhs;
-    }
+        /// Compares the centered values, as [`Self::to_signed`] does
+        #[cfg(feature = "constant_time")]
+        impl subtle::ConstantTimeGreater for $name {
+            fn ct_gt(&self, other: &Self) -> subtle::Choice {
+                self.ct_key().ct_gt(&other.ct_key())
+            }
+        }
+
+        #[cfg(feature = "constant_time")]
+        impl subtle::ConstantTimeLess for $name {}
+
+        impl std::ops::Mul<i32> for $name {
+            type Output = $name;
+
+            fn mul(self, rhs: i32) -> $name {
+                // sign extension (or truncation) keeps the value mod 2^BITS
+                let inner = self.inner.wrapping_mul(rhs as $repr);
+                $name { inner }
+            }
+        }
+
+        impl std::ops::MulAssign<i32> for $name {
+            fn mul_assign(&mut self, rhs: i32) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl std::ops::Mul<$name> for i32 {
+            type Output = $name;
+
+            fn mul(self, rhs: $name) -> $name {
+                rhs * self
+            }
+        }
+
+        impl std::ops::Mul<f64> for $name {
+            type Output = $name;
+
+            fn mul(self, rhs: f64) -> $name {
+                let v = f64::from(self) * rhs;
+                $name::from(v)
+            }
+        }
+
+        impl std::ops::Mul<$name> for f64 {
+            type Output = $name;
+
+            fn mul(self, rhs: $name) -> $name {
+                rhs * self
+            }
+        }
+
+        impl std::ops::MulAssign<f64> for $name {
+            fn mul_assign(&mut self, rhs: f64) {
+                *self = *self * rhs;
+            }
+        }
+    };
 }
 
+impl_torus!(Torus16, u16, i16);
+impl_torus!(Torus32, u32, i32);
+impl_torus!(Torus64, u64, i64);
+impl_torus!(Torus128, u128, i128);
+
+/// The default 32-bit torus.
+pub type Torus = Torus32;
+
+/// Backing integer of [`Torus`].
+pub type TorusRepr = u32;
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rng::TorusRng;
     use distr_traits::normal::NormalSample;
     use distr_traits::uniform::UniformSample;
 
@@ -194,7 +583,7 @@ mod tests {
         let t = Torus::from(0.4);
         assert_eq!(t.sign(), 1);
     }
-    
+
     #[test]
     fn test_sign_minus() {
         let t = Torus::from(0.6);
@@ -376,7 +765,7 @@ mod tests {
         let t2 = t1 * -2;
         assert_relative_eq!(f64::from(t2), 0.4, epsilon = 0.0001);
     }
-    
+
     #[test]
     fn test_mul_approx_neg_f64_1() {
         let f = 0.125;
@@ -411,20 +800,35 @@ mod tests {
     #[cfg(feature = "random")]
     #[test]
     fn test_normal() {
+        let mut rng = TorusRng::from_seed([1; 32]);
         for _ in 0..1000 {
-            let mut rng = rand::thread_rng();
             let t = Torus::normal_sample(0., 0.1, &mut rng);
             assert!(f64::from(t) >= 0.0);
             assert!(f64::from(t) < 1.0);
         }
     }
 
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_uniform_raw() {
+        use rand::{RngCore, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(5);
+        let mut raw = rng.clone();
+        for _ in 0..100 {
+            assert_eq!(Torus::uniform_sample(&mut rng).inner(), raw.next_u32());
+        }
+        let mut raw = rng.clone();
+        let t = Torus128::uniform_sample(&mut rng);
+        assert_eq!(t.inner() as u64, raw.next_u64());
+    }
+
     #[cfg(feature = "random")]
     #[test]
     fn test_normal_approx() {
+        let mut rng = TorusRng::from_seed([2; 32]);
         let sum: f64 = (0..1000)
             .map(|_| {
-                let mut rng = rand::thread_rng();
                 let t = Torus::normal_sample(0., 0.1, &mut rng);
                 f64::from(t)
             })
@@ -436,8 +840,8 @@ mod tests {
     #[cfg(feature = "random")]
     #[test]
     fn test_uniform() {
+        let mut rng = TorusRng::from_seed([3; 32]);
         for _ in 0..1000 {
-            let mut rng = rand::thread_rng();
             let t = Torus::uniform_sample(&mut rng);
             // assert!(f64::from(t) >= 0.0);
             // assert!(f64::from(t) < 1.0);
@@ -445,4 +849,479 @@ mod tests {
             assert!(f64::from(t) < 1.0);
         }
     }
+
+    #[test]
+    fn test_torus64_add_one() {
+        let t1 = Torus64::new(1 << 63);
+        let t2 = Torus64::new((1 << 63) + 1);
+        let t3 = t1 + t2;
+        assert_eq!(t3.inner, 1);
+    }
+
+    #[test]
+    fn test_torus64_mul_neg_i32() {
+        let t1 = Torus64::from(0.3);
+        let t2 = t1 * -2;
+        assert_relative_eq!(f64::from(t2), 0.4, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn test_torus16_mul_wrap() {
+        let t1 = Torus16::from(0.6);
+        let t2 = t1 * 2;
+        assert_relative_eq!(f64::from(t2), 0.2, epsilon = 0.001);
+    }
+
+    #[test]
+    fn test_torus128_neg() {
+        let t = Torus128::from(0.3);
+        assert_relative_eq!(f64::from(-t), 0.7, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn test_generic_sum() {
+        fn sum<T: TorusScalar>(values: &[f64]) -> f64 {
+            values
+                .iter()
+                .fold(T::zero(), |acc, &v| acc + T::from(v))
+                .into()
+        }
+
+        let values = [0.25, 0.5, 0.5];
+        assert_relative_eq!(sum::<Torus16>(&values), 0.25, epsilon = 0.001);
+        assert_relative_eq!(sum::<Torus32>(&values), 0.25, epsilon = 0.0001);
+        assert_relative_eq!(sum::<Torus64>(&values), 0.25, epsilon = 0.0001);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_normal_torus64() {
+        let mut rng = TorusRng::from_seed([4; 32]);
+        for _ in 0..1000 {
+            let t = Torus64::normal_sample(0., 0.1, &mut rng);
+            assert!(f64::from(t) >= 0.0);
+            assert!(f64::from(t) < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_from_float_exact_half() {
+        assert_eq!(Torus::from(0.5).inner, ZERO_POINTS_FIVE);
+        assert_eq!(Torus::from(-0.25).inner, 3 << 30);
+        assert_eq!(Torus64::from(0.5).inner, 1 << 63);
+    }
+
+    #[test]
+    fn test_from_f64_rounding() {
+        let half_ulp = 0.5 / 2f64.powi(32);
+        let t = |f, r| Torus::from_f64(f, r).inner;
+
+        assert_eq!(t(half_ulp, Rounding::NearestEven), 0);
+        assert_eq!(t(3. * half_ulp, Rounding::NearestEven), 2);
+        assert_eq!(t(3. * half_ulp, Rounding::TowardZero), 1);
+        assert_eq!(t(3. * half_ulp, Rounding::Floor), 1);
+
+        assert_eq!(t(-3. * half_ulp, Rounding::NearestEven), u32::MAX - 1);
+        assert_eq!(t(-3. * half_ulp, Rounding::TowardZero), u32::MAX);
+        assert_eq!(t(-3. * half_ulp, Rounding::Floor), u32::MAX - 1);
+
+        // rounds up to 1, which is 0 on the torus
+        assert_eq!(t(-half_ulp / 4., Rounding::NearestEven), 0);
+        assert_eq!(t(1. - half_ulp / 4., Rounding::NearestEven), 0);
+        assert_eq!(Torus64::from_f64(-1e-30, Rounding::Floor).inner, u64::MAX);
+    }
+
+    #[test]
+    fn test_from_fraction() {
+        assert_eq!(Torus::from_fraction(1, 2).inner, ZERO_POINTS_FIVE);
+        assert_eq!(Torus::from_fraction(-1, 4).inner, 3 << 30);
+        assert_eq!(Torus::from_fraction(7, 4).inner, 3 << 30);
+        // 2^32 / 3 = 1431655765.33
+        assert_eq!(Torus::from_fraction(1, 3).inner, 1_431_655_765);
+        assert_eq!(Torus::from_fraction(2, 3).inner, 2_863_311_531);
+        assert_eq!(Torus::from_fraction(-1, 3).inner, 2_863_311_531);
+        assert_eq!(Torus::from_fraction(i64::MIN, 1).inner, 0);
+        assert_eq!(Torus128::from_fraction(1, 3).inner, u128::MAX / 3);
+    }
+
+    #[test]
+    fn test_from_bits_fraction() {
+        assert_eq!(Torus::from_bits_fraction(1, 1).inner, ZERO_POINTS_FIVE);
+        assert_eq!(Torus::from_bits_fraction(-1, 2).inner, 3 << 30);
+        assert_eq!(Torus::from_bits_fraction(5, 0).inner, 0);
+        assert_eq!(Torus::from_bits_fraction(3, 33).inner, 2);
+        assert_eq!(Torus::from_bits_fraction(-3, 33).inner, u32::MAX);
+        assert_eq!(Torus16::from_bits_fraction(1, 16).inner, 1);
+    }
+
+    #[test]
+    fn test_round_trip_every_torus16() {
+        for inner in 0..=u16::MAX {
+            let t = Torus16::new(inner);
+            assert_eq!(Torus16::from(t.to_f64()), t);
+            assert_eq!(Torus16::from_bits_fraction(inner as i64, 16), t);
+            assert_eq!(Torus16::from_fraction(inner as i64, 1 << 16), t);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_dyadic() {
+        for log2_den in 0..=32 {
+            let den = 1u64 << log2_den;
+            let step = (den >> 12).max(1);
+            for k in (0..den).step_by(step as usize).chain([den - 1]) {
+                let f = k as f64 / den as f64;
+                let t = Torus::from_bits_fraction(k as i64, log2_den);
+                assert_eq!(t.to_f64(), f);
+                assert_eq!(Torus::from(f), t);
+                assert_eq!(Torus::from_fraction(k as i64, den), t);
+                assert_eq!(Torus::from_f64(f, Rounding::Floor), t);
+                assert_eq!(Torus::from(f - 1.), t);
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip_torus64_dyadic() {
+        for log2_den in [1, 17, 53] {
+            let den = 1u64 << log2_den;
+            for k in [0, 1, den / 3, den - 1] {
+                let t = Torus64::from_bits_fraction(k as i64, log2_den);
+                assert_eq!(Torus64::from(t.to_f64()), t);
+                assert_eq!(Torus64::from_fraction(k as i64, den), t);
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip_grid_step() {
+        // log2_den == BITS is the exact grid of every width
+        for k in [0, 1, 3, 12345, i64::MAX, -1, i64::MIN] {
+            let t = Torus16::from_bits_fraction(k, 16);
+            assert_eq!(t, Torus16::new(k as u16));
+            assert_eq!(Torus16::from_bits_fraction(t.inner() as i64, 16), t);
+            let t = Torus::from_bits_fraction(k, 32);
+            assert_eq!(t, Torus::new(k as u32));
+            assert_eq!(Torus::from_bits_fraction(t.inner() as i64, 32), t);
+            let t = Torus64::from_bits_fraction(k, 64);
+            assert_eq!(t, Torus64::new(k as u64));
+            assert_eq!(Torus64::from_bits_fraction(t.inner() as i64, 64), t);
+            let t = Torus128::from_bits_fraction(k, 128);
+            assert_eq!(t, Torus128::new(k as u128));
+            assert_eq!(Torus128::from_bits_fraction(t.to_signed() as i64, 128), t);
+        }
+        assert_eq!(Torus128::from_bits_fraction(1, 127), Torus128::new(2));
+        // below the grid step, rounded
+        assert_eq!(Torus16::from_bits_fraction(1, 127), Torus16::new(0));
+        assert_eq!(Torus::from_bits_fraction(i64::MAX, 127), Torus::new(0));
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_round_trip_random() {
+        use rand::Rng;
+
+        let mut rng = TorusRng::from_seed([5; 32]);
+        for _ in 0..10000 {
+            let t = Torus::new(rng.gen());
+            assert_eq!(Torus::from(f64::from(t)), t);
+        }
+    }
+
+    #[test]
+    fn test_overflowing_add_sub() {
+        let half = Torus::new(ZERO_POINTS_FIVE);
+        assert_eq!(
+            half.overflowing_add(Torus::new(1)),
+            (Torus::new(ZERO_POINTS_FIVE + 1), false)
+        );
+        assert_eq!(half.overflowing_add(half), (Torus::new(0), true));
+        assert_eq!(
+            Torus::new(0).overflowing_sub(Torus::new(1)),
+            (Torus::new(u32::MAX), true)
+        );
+        assert_eq!(half.checked_add(half), None);
+        assert_eq!(
+            half.checked_sub(Torus::new(1)),
+            Some(Torus::new(ZERO_POINTS_FIVE - 1))
+        );
+    }
+
+    #[test]
+    fn test_overflowing_mul_int() {
+        let quarter = Torus::new(1 << 30);
+        assert_eq!(quarter.overflowing_mul_int(3), (Torus::new(3 << 30), false));
+        assert_eq!(quarter.overflowing_mul_int(4), (Torus::new(0), true));
+        assert_eq!(quarter.overflowing_mul_int(-1), (Torus::new(3 << 30), true));
+        assert_eq!(
+            Torus::new(0).overflowing_mul_int(-7),
+            (Torus::new(0), false)
+        );
+        assert_eq!(quarter.checked_mul_int(5), None);
+        assert_eq!(
+            Torus128::new(1 << 126).overflowing_mul_int(-1),
+            (Torus128::new(3 << 126), true)
+        );
+        assert_eq!(
+            Torus128::new(u128::MAX).overflowing_mul_int(i32::MAX),
+            (
+                Torus128::new(u128::MAX.wrapping_mul(i32::MAX as u128)),
+                true
+            )
+        );
+
+        // multipliers wider than the torus
+        let one = Torus16::new(1);
+        assert_eq!(one.overflowing_mul_int(65535), (Torus16::new(65535), false));
+        assert_eq!(one.overflowing_mul_int(65536), (Torus16::new(0), true));
+        assert_eq!(one.overflowing_mul_int(65537), (Torus16::new(1), true));
+        assert_eq!(one.overflowing_mul_int(-65536), (Torus16::new(0), true));
+        assert_eq!(one.checked_mul_int(65537), None);
+        assert_eq!(one.checked_mul_int(i32::MAX), None);
+        assert_eq!(
+            Torus16::new(0).checked_mul_int(1 << 20),
+            Some(Torus16::new(0))
+        );
+        assert_eq!(
+            Torus16::new(3).overflowing_mul_int(-(1 << 16) - 1),
+            (Torus16::new(3).overflowing_mul_int(-1).0, true)
+        );
+    }
+
+    #[test]
+    fn test_saturating() {
+        let three_quarters = Torus::new(3 << 30);
+        assert_eq!(
+            three_quarters.saturating_add(three_quarters),
+            Torus::new(u32::MAX)
+        );
+        assert_eq!(Torus::new(1).saturating_sub(three_quarters), Torus::new(0));
+    }
+
+    #[test]
+    fn test_wraps_past_half() {
+        let eps = Torus::new(1 << 20);
+        let almost_half = Torus::new(ZERO_POINTS_FIVE - 1);
+        assert!(!eps.wraps_past_half(eps));
+        assert!(!eps.wraps_past_half(-eps));
+        assert!(almost_half.wraps_past_half(eps));
+        assert!((-almost_half).wraps_past_half(-eps));
+        // crossing 0 is not a wrap in the centered view
+        assert!(!(-eps).wraps_past_half(eps + eps));
+    }
+
+    #[test]
+    fn test_distance() {
+        let a = Torus::from(0.125);
+        let b = Torus::from(0.875);
+        assert_eq!(Torus::distance(a, b), 1 << 30);
+        assert_eq!(Torus::distance(b, a), -(1 << 30));
+        assert_eq!(Torus::distance(a, a), 0);
+        assert_eq!(
+            Torus::distance(Torus::new(ZERO_POINTS_FIVE), Torus::new(0)),
+            i32::MIN
+        );
+        assert_eq!(Torus64::distance(Torus64::new(0), Torus64::new(3)), -3);
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(Torus::new(0).sign(), 1);
+        assert_eq!(Torus::new(ZERO_POINTS_FIVE - 1).sign(), 1);
+        assert_eq!(Torus::new(ZERO_POINTS_FIVE).sign(), -1);
+        assert_eq!(Torus::new(u32::MAX).sign(), -1);
+        assert_eq!(Torus128::new(u128::MAX >> 1).sign(), 1);
+    }
+
+    #[test]
+    fn test_signed() {
+        assert_eq!(Torus::new(ZERO_POINTS_FIVE).to_signed(), i32::MIN);
+        assert_eq!(Torus::new(u32::MAX).to_signed(), -1);
+        assert_eq!(Torus::from(0.25).to_signed(), 1 << 30);
+        for x in [i32::MIN, -12345, -1, 0, 1, i32::MAX] {
+            assert_eq!(Torus::from_signed(x).to_signed(), x);
+        }
+        assert_eq!(Torus16::from_signed(-1), Torus16::new(u16::MAX));
+        assert_eq!(Torus64::from_signed(-2).to_signed(), -2);
+    }
+
+    #[test]
+    fn test_centered_f64() {
+        assert_eq!(Torus::from(0.75).to_centered_f64(), -0.25);
+        assert_eq!(Torus::from(0.25).to_centered_f64(), 0.25);
+        assert_eq!(Torus::new(ZERO_POINTS_FIVE).to_centered_f64(), -0.5);
+        assert_eq!(Torus64::from(0.875).to_centered_f64(), -0.125);
+    }
+
+    #[test]
+    fn test_abs_distance_to_zero() {
+        assert_eq!(Torus::new(0).abs_distance_to_zero(), 0);
+        assert_eq!(Torus::new(u32::MAX).abs_distance_to_zero(), 1);
+        assert_eq!(Torus::new(7).abs_distance_to_zero(), 7);
+        assert_eq!(
+            Torus::new(ZERO_POINTS_FIVE).abs_distance_to_zero(),
+            ZERO_POINTS_FIVE
+        );
+        assert_eq!(
+            Torus::from(0.75).abs_distance_to_zero(),
+            Torus::from(0.25).inner()
+        );
+    }
+
+    #[test]
+    fn test_mod_switch() {
+        assert_eq!(Torus::new(0).mod_switch(4), 0);
+        assert_eq!(Torus::new(1 << 28).mod_switch(4), 1);
+        assert_eq!(Torus::new((1 << 28) - (1 << 26)).mod_switch(4), 1);
+        assert_eq!(Torus::new(ZERO_POINTS_FIVE).mod_switch(4), 8);
+        // ties up
+        assert_eq!(Torus::new(1 << 27).mod_switch(4), 1);
+        assert_eq!(Torus::new((1 << 27) - 1).mod_switch(4), 0);
+        assert_eq!(Torus::new(0xdead_beef).mod_switch(32), 0xdead_beef);
+        assert_eq!(Torus::new(0xdead_beef).mod_switch(0), 0);
+        assert_eq!(Torus128::new(3 << 125).mod_switch(4), 6);
+    }
+
+    #[test]
+    fn test_mod_switch_wraps_to_zero() {
+        // rounds up to 2^log_modulus, which is 0
+        assert_eq!(Torus::new(u32::MAX).mod_switch(4), 0);
+        assert_eq!(Torus::new(u32::MAX - (1 << 27) + 1).mod_switch(4), 0);
+        assert_eq!(Torus::new(u32::MAX - (1 << 27)).mod_switch(4), 15);
+        assert_eq!(Torus::new(u32::MAX).mod_switch(31), 0);
+        assert_eq!(Torus16::new(u16::MAX).mod_switch(11), 0);
+    }
+
+    #[test]
+    fn test_round_to_bits() {
+        assert_eq!(
+            Torus::new((3 << 29) + 12345).round_to_bits(3),
+            Torus::new(3 << 29)
+        );
+        assert_eq!(
+            Torus::new((3 << 29) - 12345).round_to_bits(3),
+            Torus::new(3 << 29)
+        );
+        assert_eq!(Torus::new(u32::MAX).round_to_bits(3), Torus::new(0));
+        assert_eq!(Torus::new(12345).round_to_bits(32), Torus::new(12345));
+        assert_eq!(Torus::new(12345).round_to_bits(0), Torus::new(0));
+        assert_eq!(Torus::new(u32::MAX).round_to_bits(1), Torus::new(0));
+        assert_eq!(Torus::new(3 << 30).round_to_bits(1), Torus::new(0));
+        assert_eq!(
+            Torus64::new(u64::MAX - 5).round_to_bits(60),
+            Torus64::new(0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "modulus exceeds torus precision")]
+    fn test_round_to_too_many_bits() {
+        Torus::new(12345).round_to_bits(33);
+    }
+
+    #[test]
+    fn test_slices() {
+        let ts = [0, 1 << 28, ZERO_POINTS_FIVE, u32::MAX].map(Torus::new);
+        assert_eq!(Torus::mod_switch_slice(&ts, 4), vec![0, 1, 8, 0]);
+        assert_eq!(
+            Torus::round_slice_to_bits(&ts, 3),
+            [0, 1 << 29, ZERO_POINTS_FIVE, 0].map(Torus::new).to_vec()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_compact() {
+        let t = Torus::new(0xdead_beef);
+        let bytes = bincode::serialize(&t).unwrap();
+        assert_eq!(bytes, 0xdead_beefu32.to_le_bytes());
+        assert_eq!(bincode::deserialize::<Torus>(&bytes).unwrap(), t);
+
+        let t = Torus128::new(u128::MAX - 3);
+        let bytes = bincode::serialize(&t).unwrap();
+        assert_eq!(bincode::deserialize::<Torus128>(&bytes).unwrap(), t);
+        assert!(bincode::deserialize::<Torus>(&bytes[..3]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json() {
+        assert_eq!(serde_json::to_string(&Torus::from(0.25)).unwrap(), "0.25");
+        for inner in [0, 1, 0xdead_beef, u32::MAX] {
+            let json = serde_json::to_string(&Torus::new(inner)).unwrap();
+            assert_eq!(
+                serde_json::from_str::<Torus>(&json).unwrap(),
+                Torus::new(inner)
+            );
+        }
+        // raw values are accepted too
+        assert_eq!(
+            serde_json::from_str::<Torus>("1073741824").unwrap(),
+            Torus::from(0.25)
+        );
+
+        let t = Torus64::new(u64::MAX - 3);
+        let json = serde_json::to_string(&t).unwrap();
+        assert_eq!(json, (u64::MAX - 3).to_string());
+        assert_eq!(serde_json::from_str::<Torus64>(&json).unwrap(), t);
+
+        let t = Torus128::new(u128::MAX - 3);
+        let json = serde_json::to_string(&t).unwrap();
+        assert_eq!(serde_json::from_str::<Torus128>(&json).unwrap(), t);
+
+        for invalid in ["1.5", "-0.25", "4294967296", "\"0.5\""] {
+            assert!(
+                serde_json::from_str::<Torus>(invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[cfg(feature = "constant_time")]
+    #[test]
+    fn test_constant_time_matches() {
+        use subtle::{
+            ConditionallyNegatable, ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater,
+            ConstantTimeLess,
+        };
+
+        let values = [
+            0,
+            1,
+            12345,
+            (1 << 31) - 1,
+            1 << 31,
+            (1 << 31) + 1,
+            0xdead_beef,
+            u32::MAX,
+        ];
+        for &a in &values {
+            let a = Torus::new(a);
+            assert_eq!(a.ct_sign(), a.sign());
+            assert_eq!(bool::from(a.ct_is_negative()), a.to_signed() < 0);
+
+            let mut negated = a;
+            negated.conditional_negate(0.into());
+            assert_eq!(negated, a);
+            negated.conditional_negate(1.into());
+            assert_eq!(negated, -a);
+
+            for &b in &values {
+                let b = Torus::new(b);
+                assert_eq!(bool::from(a.ct_eq(&b)), a == b);
+                assert_eq!(bool::from(a.ct_gt(&b)), a.to_signed() > b.to_signed());
+                assert_eq!(bool::from(a.ct_lt(&b)), a.to_signed() < b.to_signed());
+                assert_eq!(Torus::conditional_select(&a, &b, 0.into()), a);
+                assert_eq!(Torus::conditional_select(&a, &b, 1.into()), b);
+            }
+        }
+
+        for t in [Torus16::new(0x8001), Torus16::new(5)] {
+            assert_eq!(t.ct_sign(), t.sign());
+        }
+        let t = Torus128::new(u128::MAX);
+        assert_eq!(t.ct_sign(), t.sign());
+        assert!(bool::from(t.ct_lt(&Torus128::new(0))));
+    }
 }
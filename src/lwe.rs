@@ -0,0 +1,580 @@
+#[cfg(feature = "random")]
+use crate::keygen::{BinaryKey, KeyDistribution};
+#[cfg(feature = "random")]
+use crate::slice::fill_uniform;
+use crate::slice::{add_assign_slice, dot_product_int, sub_assign_slice};
+use crate::wire::{Kind, WireError, WireFormat, WireReader, WireWriter};
+use crate::Torus;
+use num_traits::identities::Zero;
+use std::io::{Read, Write};
+
+/// Secret key s in Z^n, with coefficients from a [`KeyDistribution`],
+/// binary by default
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LweSecretKey {
+    coefs: Vec<i32>,
+}
+
+/// LWE sample (a, b) with b = <a, s> + m + e
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LweCiphertext {
+    pub mask: Vec<Torus>,
+    pub body: Torus,
+}
+
+/// Seed of the CSPRNG expanding the mask of seeded ciphertexts
+pub type Seed = [u8; 32];
+
+/// LWE sample whose mask is expanded from a public seed, so only the seed
+/// and the body are stored
+#[cfg(feature = "random")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SeededLweCiphertextRaw"))]
+pub struct SeededLweCiphertext {
+    pub seed: Seed,
+    pub dimension: usize,
+    pub body: Torus,
+}
+
+/// Largest mask, in torus elements, that a deserialized seeded ciphertext
+/// may expand to, far above any LWE or extracted key dimension
+#[cfg(feature = "random")]
+pub const MAX_SEEDED_DIMENSION: usize = 1 << 20;
+
+/// Unchecked fields of a deserialized [`SeededLweCiphertext`]
+#[cfg(all(feature = "random", feature = "serde"))]
+#[derive(serde::Deserialize)]
+struct SeededLweCiphertextRaw {
+    seed: Seed,
+    dimension: usize,
+    body: Torus,
+}
+
+#[cfg(all(feature = "random", feature = "serde"))]
+impl TryFrom<SeededLweCiphertextRaw> for SeededLweCiphertext {
+    type Error = &'static str;
+
+    // unlike the wire reader, nothing bounds the mask by the input size
+    fn try_from(raw: SeededLweCiphertextRaw) -> Result<Self, Self::Error> {
+        if raw.dimension > MAX_SEEDED_DIMENSION {
+            return Err("dimension too large");
+        }
+        Ok(Self {
+            seed: raw.seed,
+            dimension: raw.dimension,
+            body: raw.body,
+        })
+    }
+}
+
+/// TorusRng stream from the seed, fed to fill_uniform
+#[cfg(feature = "random")]
+fn expand_mask(seed: Seed, dimension: usize) -> Vec<Torus> {
+    let mut state = crate::rng::TorusRng::from_seed(seed);
+    let mut mask = vec![Torus::zero(); dimension];
+    fill_uniform(&mut mask, &mut state);
+    mask
+}
+
+/// sum of a_i * s_i
+fn dot(mask: &[Torus], key: &[i32]) -> Torus {
+    assert_eq!(mask.len(), key.len(), "dimension mismatch");
+    dot_product_int(mask, key)
+}
+
+impl LweSecretKey {
+    pub fn from_coefs(coefs: Vec<i32>) -> Self {
+        Self { coefs }
+    }
+
+    #[cfg(feature = "random")]
+    pub fn generate(dimension: usize, state: &mut impl rand::Rng) -> Self {
+        Self::generate_with(&BinaryKey, dimension, state)
+    }
+
+    #[cfg(feature = "random")]
+    pub fn generate_with(
+        distribution: &impl KeyDistribution,
+        dimension: usize,
+        state: &mut impl rand::Rng,
+    ) -> Self {
+        Self::from_coefs(distribution.sample_coefs(dimension, state))
+    }
+
+    /// Same key for the same seed
+    #[cfg(feature = "random")]
+    pub fn from_seed(distribution: &impl KeyDistribution, dimension: usize, seed: Seed) -> Self {
+        Self::from_coefs(distribution.sample_coefs_seeded(dimension, seed))
+    }
+
+    /// Same key as [`Self::generate`] for the same state: both take each bit
+    /// from the top bit of a word, here without going through bool
+    #[cfg(all(feature = "random", feature = "constant_time"))]
+    pub fn generate_ct(dimension: usize, state: &mut impl rand::Rng) -> Self {
+        let coefs = (0..dimension)
+            .map(|_| (state.next_u32() >> 31) as i32)
+            .collect();
+        Self { coefs }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.coefs.len()
+    }
+
+    pub fn coefs(&self) -> &[i32] {
+        &self.coefs
+    }
+
+    #[cfg(feature = "random")]
+    pub fn encrypt(
+        &self,
+        message: Torus,
+        noise_std: f64,
+        state: &mut impl rand::Rng,
+    ) -> LweCiphertext {
+        use distr_traits::normal::NormalSample;
+
+        let mut mask = vec![Torus::zero(); self.dimension()];
+        fill_uniform(&mut mask, state);
+        let noise = Torus::normal_sample(0., noise_std, state);
+        self.encrypt_with_mask(mask, message, noise)
+    }
+
+    /// [`Self::encrypt`] with the mask and the noise drawn from separate
+    /// generators, e.g. two forks of a [`TorusRng`](crate::rng::TorusRng),
+    /// so drawing more of one does not shift the other
+    #[cfg(feature = "random")]
+    pub fn encrypt_with_streams(
+        &self,
+        message: Torus,
+        noise_std: f64,
+        mask_state: &mut impl rand::Rng,
+        noise_state: &mut impl rand::Rng,
+    ) -> LweCiphertext {
+        use distr_traits::normal::NormalSample;
+
+        let mut mask = vec![Torus::zero(); self.dimension()];
+        fill_uniform(&mut mask, mask_state);
+        let noise = Torus::normal_sample(0., noise_std, noise_state);
+        self.encrypt_with_mask(mask, message, noise)
+    }
+
+    /// [`Self::encrypt`] with the noise drawn by the constant-time discrete
+    /// Gaussian sampler, so not the same ciphertext for the same state
+    #[cfg(all(feature = "random", feature = "constant_time"))]
+    pub fn encrypt_ct(
+        &self,
+        message: Torus,
+        noise_std: f64,
+        state: &mut impl rand::Rng,
+    ) -> LweCiphertext {
+        let mut mask = vec![Torus::zero(); self.dimension()];
+        fill_uniform(&mut mask, state);
+        let noise = crate::gaussian::encryption_noise_ct(noise_std, state);
+        self.encrypt_with_mask(mask, message, noise)
+    }
+
+    /// Encryption with the mask expanded from a fresh seed drawn from state.
+    /// The noise is still drawn from state, never from the public seed.
+    #[cfg(feature = "random")]
+    pub fn encrypt_seeded(
+        &self,
+        message: Torus,
+        noise_std: f64,
+        state: &mut impl rand::Rng,
+    ) -> SeededLweCiphertext {
+        use distr_traits::normal::NormalSample;
+
+        let seed: Seed = state.gen();
+        let mask = expand_mask(seed, self.dimension());
+        let noise = Torus::normal_sample(0., noise_std, state);
+        let ct = self.encrypt_with_mask(mask, message, noise);
+        SeededLweCiphertext {
+            seed,
+            dimension: self.dimension(),
+            body: ct.body,
+        }
+    }
+
+    #[cfg(feature = "random")]
+    fn encrypt_with_mask(&self, mask: Vec<Torus>, message: Torus, noise: Torus) -> LweCiphertext {
+        let body = dot(&mask, &self.coefs) + message + noise;
+
+        LweCiphertext { mask, body }
+    }
+
+    /// b - <a, s> = m + e
+    pub fn decrypt_phase(&self, ct: &LweCiphertext) -> Torus {
+        ct.body - dot(&ct.mask, &self.coefs)
+    }
+}
+
+impl LweCiphertext {
+    /// Noiseless encryption of message under any key of the dimension
+    pub fn trivial(message: Torus, dimension: usize) -> Self {
+        Self {
+            mask: vec![Torus::zero(); dimension],
+            body: message,
+        }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.mask.len()
+    }
+}
+
+#[cfg(feature = "random")]
+impl SeededLweCiphertext {
+    /// The full ciphertext, with the mask expanded from the seed
+    pub fn decompress(&self) -> LweCiphertext {
+        LweCiphertext {
+            mask: expand_mask(self.seed, self.dimension),
+            body: self.body,
+        }
+    }
+}
+
+impl std::ops::AddAssign<&LweCiphertext> for LweCiphertext {
+    fn add_assign(&mut self, other: &LweCiphertext) {
+        assert_eq!(self.dimension(), other.dimension(), "dimension mismatch");
+        add_assign_slice(&mut self.mask, &other.mask);
+        self.body += other.body;
+    }
+}
+
+impl std::ops::SubAssign<&LweCiphertext> for LweCiphertext {
+    fn sub_assign(&mut self, other: &LweCiphertext) {
+        assert_eq!(self.dimension(), other.dimension(), "dimension mismatch");
+        sub_assign_slice(&mut self.mask, &other.mask);
+        self.body -= other.body;
+    }
+}
+
+impl std::ops::MulAssign<i32> for LweCiphertext {
+    fn mul_assign(&mut self, rhs: i32) {
+        for a in self.mask.iter_mut() {
+            *a *= rhs;
+        }
+        self.body *= rhs;
+    }
+}
+
+impl std::ops::Add for LweCiphertext {
+    type Output = LweCiphertext;
+
+    fn add(mut self, other: LweCiphertext) -> LweCiphertext {
+        self += &other;
+        self
+    }
+}
+
+impl std::ops::Add<&LweCiphertext> for &LweCiphertext {
+    type Output = LweCiphertext;
+
+    fn add(self, other: &LweCiphertext) -> LweCiphertext {
+        let mut out = self.clone();
+        out += other;
+        out
+    }
+}
+
+impl std::ops::Sub for LweCiphertext {
+    type Output = LweCiphertext;
+
+    fn sub(mut self, other: LweCiphertext) -> LweCiphertext {
+        self -= &other;
+        self
+    }
+}
+
+impl std::ops::Sub<&LweCiphertext> for &LweCiphertext {
+    type Output = LweCiphertext;
+
+    fn sub(self, other: &LweCiphertext) -> LweCiphertext {
+        let mut out = self.clone();
+        out -= other;
+        out
+    }
+}
+
+impl std::ops::Neg for LweCiphertext {
+    type Output = LweCiphertext;
+
+    fn neg(mut self) -> LweCiphertext {
+        for a in self.mask.iter_mut() {
+            *a = -*a;
+        }
+        self.body = -self.body;
+        self
+    }
+}
+
+impl std::ops::Neg for &LweCiphertext {
+    type Output = LweCiphertext;
+
+    fn neg(self) -> LweCiphertext {
+        -self.clone()
+    }
+}
+
+impl std::ops::Mul<i32> for LweCiphertext {
+    type Output = LweCiphertext;
+
+    fn mul(mut self, rhs: i32) -> LweCiphertext {
+        self *= rhs;
+        self
+    }
+}
+
+impl std::ops::Mul<i32> for &LweCiphertext {
+    type Output = LweCiphertext;
+
+    fn mul(self, rhs: i32) -> LweCiphertext {
+        self.clone() * rhs
+    }
+}
+
+impl std::ops::Mul<LweCiphertext> for i32 {
+    type Output = LweCiphertext;
+
+    fn mul(self, rhs: LweCiphertext) -> LweCiphertext {
+        rhs * self
+    }
+}
+
+impl LweCiphertext {
+    pub(crate) fn write_payload(&self, w: &mut WireWriter<impl Write>) -> Result<(), WireError> {
+        w.torus_slice(&self.mask)?;
+        w.torus_slice(&[self.body])
+    }
+
+    pub(crate) fn read_payload(
+        r: &mut WireReader<impl Read>,
+        dimension: usize,
+    ) -> Result<Self, WireError> {
+        let mask = r.torus_vec(dimension)?;
+        let [body] = r.torus_array()?;
+        Ok(Self { mask, body })
+    }
+}
+
+impl WireFormat for LweCiphertext {
+    fn write_to(&self, writer: impl Write) -> Result<(), WireError> {
+        let mut w = WireWriter::new(writer, Kind::LweCiphertext)?;
+        w.u64(self.dimension() as u64)?;
+        self.write_payload(&mut w)
+    }
+
+    fn read_from(reader: impl Read) -> Result<Self, WireError> {
+        let mut r = WireReader::new(reader, Kind::LweCiphertext)?;
+        let dimension = r.len()?;
+        Self::read_payload(&mut r, dimension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::TorusRng;
+
+    const N: usize = 500;
+    const STD: f64 = 1e-5;
+
+    #[test]
+    fn test_trivial() {
+        let key = LweSecretKey::from_coefs(vec![1, 0, 1, 1]);
+        let ct = LweCiphertext::trivial(Torus::from(0.25), 4);
+        assert_eq!(key.decrypt_phase(&ct), Torus::from(0.25));
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_encrypt_decrypt() {
+        let mut rng = TorusRng::from_seed([1; 32]);
+        let key = LweSecretKey::generate(N, &mut rng);
+        assert!(key.coefs().iter().all(|&s| s == 0 || s == 1));
+
+        for m in [0.0, 0.125, 0.25, 0.5, 0.875] {
+            let ct = key.encrypt(Torus::from(m), STD, &mut rng);
+            assert_eq!(ct.dimension(), N);
+            let error = key.decrypt_phase(&ct) - Torus::from(m);
+            assert!(error.abs_distance_to_zero() < 1 << 22);
+        }
+    }
+
+    #[cfg(all(feature = "random", feature = "constant_time"))]
+    #[test]
+    fn test_generate_ct() {
+        use rand::SeedableRng;
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(7);
+        let mut rng_ct = rng.clone();
+        assert_eq!(
+            LweSecretKey::generate(N, &mut rng),
+            LweSecretKey::generate_ct(N, &mut rng_ct)
+        );
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_encrypt_with_streams() {
+        let rng = TorusRng::from_seed([5; 32]);
+        let key = LweSecretKey::generate(N, &mut rng.fork("key"));
+        let m = Torus::from(0.25);
+        let encrypt = |mask_label, noise_label| {
+            key.encrypt_with_streams(
+                m,
+                STD,
+                &mut rng.fork(mask_label),
+                &mut rng.fork(noise_label),
+            )
+        };
+
+        let ct = encrypt("mask", "noise");
+        let other_noise = encrypt("mask", "other noise");
+        assert_eq!(other_noise.mask, ct.mask);
+        assert_ne!(other_noise.body, ct.body);
+        // and the other way around
+        let other_mask = encrypt("other mask", "noise");
+        assert_ne!(other_mask.mask, ct.mask);
+        assert_eq!(key.decrypt_phase(&other_mask), key.decrypt_phase(&ct));
+    }
+
+    #[cfg(all(feature = "random", feature = "constant_time"))]
+    #[test]
+    fn test_encrypt_ct_noise() {
+        use crate::gaussian::DiscreteGaussian;
+
+        let mut rng = TorusRng::from_seed([4; 32]);
+        let key = LweSecretKey::generate(N, &mut rng);
+        let mut replay = rng.clone();
+        let ct = key.encrypt_ct(Torus::from(0.25), STD, &mut rng);
+
+        let mut mask = vec![Torus::zero(); N];
+        fill_uniform(&mut mask, &mut replay);
+        let noise = DiscreteGaussian::new(STD).sample_ct(&mut replay);
+        assert_eq!(ct.mask, mask);
+        assert_eq!(key.decrypt_phase(&ct), Torus::from(0.25) + noise);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_key_distributions() {
+        use crate::keygen::{GaussianKey, TernaryKey};
+
+        let mut rng = TorusRng::from_seed([2; 32]);
+        let ternary = LweSecretKey::generate_with(&TernaryKey { hamming_weight: 64 }, N, &mut rng);
+        assert_eq!(ternary.coefs().iter().filter(|&&s| s != 0).count(), 64);
+        let gaussian = LweSecretKey::generate_with(&GaussianKey { std: 3.2 }, N, &mut rng);
+
+        for key in [ternary, gaussian] {
+            for m in [0.0, 0.25, 0.625] {
+                let ct = key.encrypt(Torus::from(m), STD, &mut rng);
+                let error = key.decrypt_phase(&ct) - Torus::from(m);
+                assert!(error.abs_distance_to_zero() < 1 << 22);
+            }
+        }
+
+        let seed = [3; 32];
+        assert_eq!(
+            LweSecretKey::from_seed(&BinaryKey, N, seed),
+            LweSecretKey::from_seed(&BinaryKey, N, seed)
+        );
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_seeded() {
+        let mut rng = TorusRng::from_seed([3; 32]);
+        let key = LweSecretKey::generate(N, &mut rng);
+
+        for m in [0.0, 0.125, 0.5, 0.875] {
+            let seeded = key.encrypt_seeded(Torus::from(m), STD, &mut rng);
+            let ct = seeded.decompress();
+            assert_eq!(ct.dimension(), N);
+            assert_eq!(ct.body, seeded.body);
+            // the expansion is deterministic
+            assert_eq!(seeded.decompress(), ct);
+            let error = key.decrypt_phase(&ct) - Torus::from(m);
+            assert!(error.abs_distance_to_zero() < 1 << 22);
+        }
+
+        let a = key.encrypt_seeded(Torus::zero(), STD, &mut rng);
+        let b = key.encrypt_seeded(Torus::zero(), STD, &mut rng);
+        assert_ne!(a.seed, b.seed);
+        assert_ne!(a.decompress().mask, b.decompress().mask);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_homomorphic_ops() {
+        let mut rng = TorusRng::from_seed([4; 32]);
+        let key = LweSecretKey::generate(N, &mut rng);
+
+        let c1 = key.encrypt(Torus::from(0.125), STD, &mut rng);
+        let c2 = key.encrypt(Torus::from(0.25), STD, &mut rng);
+
+        let cases = [
+            (&c1 + &c2, 0.375),
+            (&c1 - &c2, 0.875),
+            (-&c2, 0.75),
+            (&c1 * 3, 0.375),
+            (-2 * c2.clone(), 0.5),
+            (c1.clone() + c2.clone() * -1, 0.875),
+        ];
+        for (ct, expected) in cases {
+            let phase = key.decrypt_phase(&ct);
+            assert_relative_eq!(f64::from(phase), expected, epsilon = 1e-3);
+        }
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    #[should_panic(expected = "dimension mismatch")]
+    fn test_dimension_mismatch() {
+        let mut rng = TorusRng::from_seed([5; 32]);
+        let key = LweSecretKey::generate(N, &mut rng);
+        let ct = LweCiphertext::trivial(Torus::zero(), N + 1);
+        key.decrypt_phase(&ct);
+    }
+
+    #[cfg(all(feature = "random", feature = "serde"))]
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut rng = TorusRng::from_seed([6; 32]);
+        let key = LweSecretKey::generate(N, &mut rng);
+        let ct = key.encrypt(Torus::from(0.25), STD, &mut rng);
+
+        let key_bytes = bincode::serialize(&key).unwrap();
+        let ct_bytes = bincode::serialize(&ct).unwrap();
+        let key2: LweSecretKey = bincode::deserialize(&key_bytes).unwrap();
+        let ct2: LweCiphertext = bincode::deserialize(&ct_bytes).unwrap();
+        assert_eq!(key2, key);
+        assert_eq!(ct2, ct);
+
+        let ct3: LweCiphertext =
+            serde_json::from_str(&serde_json::to_string(&ct).unwrap()).unwrap();
+        assert_eq!(ct3, ct);
+        assert_eq!(key.decrypt_phase(&ct3), key.decrypt_phase(&ct));
+    }
+
+    #[cfg(all(feature = "random", feature = "serde"))]
+    #[test]
+    fn test_serde_seeded() {
+        let mut rng = TorusRng::from_seed([7; 32]);
+        let key = LweSecretKey::generate(N, &mut rng);
+        let ct = key.encrypt_seeded(Torus::from(0.25), STD, &mut rng);
+        let mut json = serde_json::to_value(&ct).unwrap();
+        assert_eq!(
+            serde_json::from_value::<SeededLweCiphertext>(json.clone()).unwrap(),
+            ct
+        );
+
+        // would allocate the whole mask on decompress
+        json["dimension"] = serde_json::json!(u64::MAX);
+        assert!(serde_json::from_value::<SeededLweCiphertext>(json).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+//! Exact negacyclic polynomial multiplication with a number theoretic transform.
+//!
+//! The product of an integer polynomial and a (signed lifted) torus polynomial
+//! has integer coefficients bounded by N * 2^31 * 2^31 < 2^74 for N <= 2^11.
+//! It is computed exactly modulo two 62-bit NTT friendly primes, recombined
+//! with the CRT into a signed integer and then reduced mod 2^32, so the result
+//! is bit-for-bit the schoolbook product.
+
+use crate::polynomial::{IntPolynomial, PolynomialMultiplier, TorusPolynomial};
+use crate::Torus;
+
+/// (p, generator of (Z/pZ)^*), p = k * 2^32 + 1
+const PRIMES: [(u64, u64); 2] = [(0x3fff_ffee_0000_0001, 3), (0x3fff_ffb4_0000_0001, 19)];
+
+fn mul_mod(a: u64, b: u64, p: u64) -> u64 {
+    ((a as u128 * b as u128) % p as u128) as u64
+}
+
+fn add_mod(a: u64, b: u64, p: u64) -> u64 {
+    let c = a + b;
+    if c >= p {
+        c - p
+    } else {
+        c
+    }
+}
+
+fn sub_mod(a: u64, b: u64, p: u64) -> u64 {
+    if a >= b {
+        a - b
+    } else {
+        a + p - b
+    }
+}
+
+fn pow_mod(mut a: u64, mut e: u64, p: u64) -> u64 {
+    let mut r = 1;
+    while e > 0 {
+        if e & 1 == 1 {
+            r = mul_mod(r, a, p);
+        }
+        a = mul_mod(a, a, p);
+        e >>= 1;
+    }
+    r
+}
+
+fn inv_mod(a: u64, p: u64) -> u64 {
+    pow_mod(a, p - 2, p)
+}
+
+/// Twiddle factors of a negacyclic NTT of size N modulo one prime
+#[derive(Clone, Debug)]
+struct PrimePlan {
+    p: u64,
+    /// psi^j, psi a primitive 2N-th root of unity
+    twist: Vec<u64>,
+    /// psi^-j * N^-1
+    untwist: Vec<u64>,
+    /// omega^k, omega = psi^2, for k < N/2
+    roots: Vec<u64>,
+    inv_roots: Vec<u64>,
+}
+
+impl PrimePlan {
+    fn new(n: usize, p: u64, generator: u64) -> Self {
+        let psi = pow_mod(generator, (p - 1) / (2 * n as u64), p);
+        let psi_inv = inv_mod(psi, p);
+        let n_inv = inv_mod(n as u64, p);
+        let omega = mul_mod(psi, psi, p);
+        let omega_inv = inv_mod(omega, p);
+
+        let powers = |base: u64, len: usize, scale: u64| {
+            let mut out = Vec::with_capacity(len);
+            let mut x = scale;
+            for _ in 0..len {
+                out.push(x);
+                x = mul_mod(x, base, p);
+            }
+            out
+        };
+
+        Self {
+            p,
+            twist: powers(psi, n, 1),
+            untwist: powers(psi_inv, n, n_inv),
+            roots: powers(omega, n / 2, 1),
+            inv_roots: powers(omega_inv, n / 2, 1),
+        }
+    }
+
+    /// In place cyclic NTT (or its inverse, unnormalized)
+    fn ntt(&self, buf: &mut [u64], bit_reverse: &[usize], inverse: bool) {
+        let p = self.p;
+        let n = buf.len();
+        let roots = if inverse {
+            &self.inv_roots
+        } else {
+            &self.roots
+        };
+
+        for (i, &j) in bit_reverse.iter().enumerate() {
+            if i < j {
+                buf.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= n {
+            let half = len / 2;
+            let step = n / len;
+            for start in (0..n).step_by(len) {
+                for k in 0..half {
+                    let u = buf[start + k];
+                    let v = mul_mod(buf[start + k + half], roots[k * step], p);
+                    buf[start + k] = add_mod(u, v, p);
+                    buf[start + k + half] = sub_mod(u, v, p);
+                }
+            }
+            len *= 2;
+        }
+    }
+
+    /// Negacyclic product of two polynomials given by residues mod p
+    fn negacyclic_mul(&self, a: &[u64], b: &[u64], bit_reverse: &[usize]) -> Vec<u64> {
+        let p = self.p;
+        let mut fa: Vec<u64> = a
+            .iter()
+            .zip(&self.twist)
+            .map(|(&x, &w)| mul_mod(x, w, p))
+            .collect();
+        let mut fb: Vec<u64> = b
+            .iter()
+            .zip(&self.twist)
+            .map(|(&x, &w)| mul_mod(x, w, p))
+            .collect();
+        self.ntt(&mut fa, bit_reverse, false);
+        self.ntt(&mut fb, bit_reverse, false);
+
+        for (x, &y) in fa.iter_mut().zip(&fb) {
+            *x = mul_mod(*x, y, p);
+        }
+
+        self.ntt(&mut fa, bit_reverse, true);
+        for (x, &w) in fa.iter_mut().zip(&self.untwist) {
+            *x = mul_mod(*x, w, p);
+        }
+        fa
+    }
+}
+
+/// Precomputed tables for exact negacyclic products of size N.
+/// Build once and reuse across multiplications.
+#[derive(Clone, Debug)]
+pub struct NttPlan<const N: usize> {
+    primes: [PrimePlan; 2],
+    bit_reverse: Vec<usize>,
+    /// p0^-1 mod p1
+    crt_coef: u64,
+}
+
+impl<const N: usize> Default for NttPlan<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> NttPlan<N> {
+    pub fn new() -> Self {
+        assert!(N.is_power_of_two(), "N must be a power of two");
+        assert!(N <= 1 << 11, "N is too large for the CRT modulus");
+
+        let bits = N.trailing_zeros();
+        let bit_reverse = (0..N)
+            .map(|i| {
+                if bits == 0 {
+                    0
+                } else {
+                    i.reverse_bits() >> (usize::BITS - bits)
+                }
+            })
+            .collect();
+
+        let [(p0, g0), (p1, g1)] = PRIMES;
+        Self {
+            primes: [PrimePlan::new(N, p0, g0), PrimePlan::new(N, p1, g1)],
+            bit_reverse,
+            crt_coef: inv_mod(p0 % p1, p1),
+        }
+    }
+
+    /// Signed integer x in residues mod p
+    fn residue(x: i64, p: u64) -> u64 {
+        x.rem_euclid(p as i64) as u64
+    }
+
+    /// Recombine residues (r0 mod p0, r1 mod p1) into the centered integer mod 2^32
+    fn crt(&self, r0: u64, r1: u64) -> u32 {
+        let (p0, p1) = (self.primes[0].p, self.primes[1].p);
+        let t = mul_mod(sub_mod(r1, r0 % p1, p1), self.crt_coef, p1);
+        let x = r0 as u128 + p0 as u128 * t as u128;
+        let modulus = p0 as u128 * p1 as u128;
+        let x = if x > modulus / 2 {
+            x as i128 - modulus as i128
+        } else {
+            x as i128
+        };
+        x as u32
+    }
+}
+
+impl<const N: usize> PolynomialMultiplier<N> for NttPlan<N> {
+    fn mul(&self, a: &IntPolynomial<N>, b: &TorusPolynomial<N>) -> TorusPolynomial<N> {
+        let [r0, r1] = [&self.primes[0], &self.primes[1]].map(|plan| {
+            let ra: Vec<u64> = a
+                .coefs
+                .iter()
+                .map(|&x| Self::residue(x as i64, plan.p))
+                .collect();
+            let rb: Vec<u64> = b
+                .coefs
+                .iter()
+                .map(|x| Self::residue(x.to_signed() as i64, plan.p))
+                .collect();
+            plan.negacyclic_mul(&ra, &rb, &self.bit_reverse)
+        });
+
+        let mut out = TorusPolynomial::new([Torus::new(0); N]);
+        for (c, (&x0, &x1)) in out.coefs.iter_mut().zip(r0.iter().zip(&r1)) {
+            *c = Torus::new(self.crt(x0, x1));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::FftPlan;
+    use crate::polynomial::NaiveMultiplier;
+    use crate::rng::TorusRng;
+    use distr_traits::uniform::UniformSample;
+    use rand::Rng;
+
+    #[test]
+    fn test_primes() {
+        for (p, g) in PRIMES {
+            assert_eq!(pow_mod(g, p - 1, p), 1);
+            assert_ne!(pow_mod(g, (p - 1) / 2, p), 1);
+            assert_eq!((p - 1) % (1 << 32), 0);
+        }
+    }
+
+    #[test]
+    fn test_small_exact() {
+        let plan = NttPlan::<4>::new();
+        let a = IntPolynomial::new([1, 2, -3, 4]);
+        let b = TorusPolynomial::new([5, 6, 7, u32::MAX].map(Torus::new));
+        assert_eq!(plan.mul(&a, &b), b * a);
+    }
+
+    #[test]
+    fn test_degree_one() {
+        let plan = NttPlan::<1>::new();
+        let a = IntPolynomial::new([-3]);
+        let b = TorusPolynomial::new([Torus::new(5)]);
+        assert_eq!(plan.mul(&a, &b), b * a);
+    }
+
+    #[test]
+    fn test_extreme_coefficients() {
+        let plan = NttPlan::<2048>::new();
+        let a = IntPolynomial::new([i32::MIN; 2048]);
+        let b = TorusPolynomial::new([Torus::new(1 << 31); 2048]);
+        assert_eq!(plan.mul(&a, &b), b * a);
+    }
+
+    #[cfg(feature = "random")]
+    fn random_pair<const N: usize>(digit_bound: i32) -> (IntPolynomial<N>, TorusPolynomial<N>) {
+        let mut rng = TorusRng::from_seed([1; 32]);
+        let a = IntPolynomial::new(std::array::from_fn(|_| {
+            rng.gen_range(-digit_bound..=digit_bound)
+        }));
+        let b = TorusPolynomial::new(std::array::from_fn(|_| Torus::uniform_sample(&mut rng)));
+        (a, b)
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_random_full_range_exact() {
+        let plan = NttPlan::<1024>::new();
+        let (a, b) = random_pair::<1024>(i32::MAX);
+        assert_eq!(plan.mul(&a, &b), NaiveMultiplier.mul(&a, &b));
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_backends_agree() {
+        fn check<const N: usize>(backend: &impl PolynomialMultiplier<N>) {
+            let (a, b) = random_pair::<N>(1 << 9);
+            assert_eq!(backend.mul(&a, &b), NaiveMultiplier.mul(&a, &b));
+        }
+
+        check(&NttPlan::<512>::new());
+        check(&FftPlan::<512>::new());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::{Torus, TorusRepr};
+use num_traits::identities::{ConstZero, Zero};
+
+/// Polynomial with Torus coefficients in T[X]/(X^N+1)
+/// coefs[i] is the coefficient of X^i
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TorusPolynomial<const N: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    pub coefs: [Torus; N],
+}
+
+/// Polynomial with integer coefficients in Z[X]/(X^N+1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntPolynomial<const N: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    pub coefs: [i32; N],
+}
+
+/// Backend computing negacyclic products of integer and torus polynomials
+pub trait PolynomialMultiplier<const N: usize> {
+    /// a * b mod X^N+1
+    fn mul(&self, a: &IntPolynomial<N>, b: &TorusPolynomial<N>) -> TorusPolynomial<N>;
+}
+
+/// Schoolbook multiplication, the reference backend
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NaiveMultiplier;
+
+impl<const N: usize> PolynomialMultiplier<N> for NaiveMultiplier {
+    fn mul(&self, a: &IntPolynomial<N>, b: &TorusPolynomial<N>) -> TorusPolynomial<N> {
+        b.naive_mul(a)
+    }
+}
+
+/// serde only implements arrays up to 32 elements, so [T; N] goes through a
+/// tuple of N elements
+#[cfg(feature = "serde")]
+mod serde_array {
+    use serde::de::{Deserialize, Deserializer, Error, SeqAccess, Visitor};
+    use serde::ser::{Serialize, SerializeTuple, Serializer};
+    use std::marker::PhantomData;
+
+    pub fn serialize<S, T, const N: usize>(array: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for x in array {
+            tuple.serialize_element(x)?;
+        }
+        tuple.end()
+    }
+
+    struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+        type Value = [T; N];
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "an array of length {}", N)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[T; N], A::Error> {
+            let mut out = Vec::with_capacity(N);
+            while let Some(x) = seq.next_element()? {
+                if out.len() == N {
+                    return Err(A::Error::invalid_length(N + 1, &self));
+                }
+                out.push(x);
+            }
+            let len = out.len();
+            out.try_into()
+                .map_err(|_| A::Error::invalid_length(len, &self))
+        }
+    }
+
+    pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+    }
+}
+
+/// Multiply coefs by X^k, k is taken mod 2N.
+/// X^N = -1, so coefficients passing over X^N get negated.
+fn rotate<T, const N: usize>(coefs: &[T; N], k: usize, neg: impl Fn(T) -> T) -> [T; N]
+where
+    T: Copy,
+{
+    let k = k % (2 * N);
+    let mut out = *coefs;
+    for (i, &c) in coefs.iter().enumerate() {
+        let j = (i + k) % (2 * N);
+        if j < N {
+            out[j] = c;
+        } else {
+            out[j - N] = neg(c);
+        }
+    }
+    out
+}
+
+impl<const N: usize> TorusPolynomial<N> {
+    pub fn new(coefs: [Torus; N]) -> Self {
+        Self { coefs }
+    }
+
+    /// X^k * self
+    pub fn mul_by_monomial(&self, k: usize) -> Self {
+        Self::new(rotate(&self.coefs, k, |c: Torus| -c))
+    }
+
+    /// Every coefficient switched to Z_(2^log_modulus), see [`Torus::mod_switch`]
+    pub fn mod_switch(&self, log_modulus: u32) -> [TorusRepr; N] {
+        self.coefs.map(|c| c.mod_switch(log_modulus))
+    }
+
+    /// Every coefficient rounded to the nearest multiple of 2^-bits
+    pub fn round_to_bits(&self, bits: u32) -> Self {
+        Self::new(self.coefs.map(|c| c.round_to_bits(bits)))
+    }
+
+    /// Schoolbook negacyclic product, O(N^2)
+    pub fn naive_mul(&self, rhs: &IntPolynomial<N>) -> Self {
+        let mut out = Self::ZERO;
+        for (i, &a) in self.coefs.iter().enumerate() {
+            for (j, &b) in rhs.coefs.iter().enumerate() {
+                if i + j < N {
+                    out.coefs[i + j] += a * b;
+                } else {
+                    out.coefs[i + j - N] -= a * b;
+                }
+            }
+        }
+        out
+    }
+}
+
+impl<const N: usize> IntPolynomial<N> {
+    pub fn new(coefs: [i32; N]) -> Self {
+        Self { coefs }
+    }
+
+    /// X^k * self
+    pub fn mul_by_monomial(&self, k: usize) -> Self {
+        Self::new(rotate(&self.coefs, k, |c: i32| c.wrapping_neg()))
+    }
+}
+
+#[cfg(feature = "random")]
+impl<const N: usize> distr_traits::uniform::UniformSample for TorusPolynomial<N> {
+    fn uniform_sample(state: &mut impl rand::Rng) -> Self {
+        let mut coefs = [Torus::ZERO; N];
+        crate::slice::fill_uniform(&mut coefs, state);
+        Self::new(coefs)
+    }
+}
+
+/// Every coefficient is sampled independently
+#[cfg(feature = "random")]
+impl<const N: usize> distr_traits::normal::NormalSample for TorusPolynomial<N> {
+    type Mean = f64;
+    type Variance = f64;
+
+    fn normal_sample(mean: f64, std: f64, state: &mut impl rand::Rng) -> Self {
+        Self::new(std::array::from_fn(|_| {
+            Torus::normal_sample(mean, std, state)
+        }))
+    }
+}
+
+impl<const N: usize> ConstZero for TorusPolynomial<N> {
+    const ZERO: Self = Self {
+        coefs: [Torus::ZERO; N],
+    };
+}
+
+impl<const N: usize> Zero for TorusPolynomial<N> {
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    fn is_zero(&self) -> bool {
+        self.coefs.iter().all(|c| c.is_zero())
+    }
+}
+
+impl<const N: usize> ConstZero for IntPolynomial<N> {
+    const ZERO: Self = Self { coefs: [0; N] };
+}
+
+impl<const N: usize> Zero for IntPolynomial<N> {
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    fn is_zero(&self) -> bool {
+        self.coefs.iter().all(|&c| c == 0)
+    }
+}
+
+impl<const N: usize> std::ops::Index<usize> for TorusPolynomial<N> {
+    type Output = Torus;
+
+    fn index(&self, index: usize) -> &Torus {
+        &self.coefs[index]
+    }
+}
+
+impl<const N: usize> std::ops::IndexMut<usize> for TorusPolynomial<N> {
+    fn index_mut(&mut self, index: usize) -> &mut Torus {
+        &mut self.coefs[index]
+    }
+}
+
+impl<const N: usize> std::ops::Index<usize> for IntPolynomial<N> {
+    type Output = i32;
+
+    fn index(&self, index: usize) -> &i32 {
+        &self.coefs[index]
+    }
+}
+
+impl<const N: usize> std::ops::IndexMut<usize> for IntPolynomial<N> {
+    fn index_mut(&mut self, index: usize) -> &mut i32 {
+        &mut self.coefs[index]
+    }
+}
+
+impl<const N: usize> std::ops::Add for TorusPolynomial<N> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl<const N: usize> std::ops::Sub for TorusPolynomial<N> {
+    type Output = Self;
+
+    fn sub(mut self, other: Self) -> Self {
+        self -= other;
+        self
+    }
+}
+
+impl<const N: usize> std::ops::AddAssign for TorusPolynomial<N> {
+    fn add_assign(&mut self, other: Self) {
+        for (a, b) in self.coefs.iter_mut().zip(other.coefs) {
+            *a += b;
+        }
+    }
+}
+
+impl<const N: usize> std::ops::SubAssign for TorusPolynomial<N> {
+    fn sub_assign(&mut self, other: Self) {
+        for (a, b) in self.coefs.iter_mut().zip(other.coefs) {
+            *a -= b;
+        }
+    }
+}
+
+impl<const N: usize> std::ops::Neg for TorusPolynomial<N> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(self.coefs.map(|c| -c))
+    }
+}
+
+impl<const N: usize> std::ops::Mul<IntPolynomial<N>> for TorusPolynomial<N> {
+    type Output = Self;
+
+    fn mul(self, rhs: IntPolynomial<N>) -> Self {
+        self.naive_mul(&rhs)
+    }
+}
+
+impl<const N: usize> std::ops::Mul<TorusPolynomial<N>> for IntPolynomial<N> {
+    type Output = TorusPolynomial<N>;
+
+    fn mul(self, rhs: TorusPolynomial<N>) -> TorusPolynomial<N> {
+        rhs * self
+    }
+}
+
+impl<const N: usize> std::ops::Add for IntPolynomial<N> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        for (a, b) in self.coefs.iter_mut().zip(other.coefs) {
+            *a = a.wrapping_add(b);
+        }
+        self
+    }
+}
+
+impl<const N: usize> std::ops::Sub for IntPolynomial<N> {
+    type Output = Self;
+
+    fn sub(mut self, other: Self) -> Self {
+        for (a, b) in self.coefs.iter_mut().zip(other.coefs) {
+            *a = a.wrapping_sub(b);
+        }
+        self
+    }
+}
+
+impl<const N: usize> std::ops::Neg for IntPolynomial<N> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(self.coefs.map(|c| c.wrapping_neg()))
+    }
+}
+
+/// Exact torus polynomial from multiples of 1/8
+#[cfg(test)]
+pub(crate) fn eighths<const N: usize>(ks: [u32; N]) -> TorusPolynomial<N> {
+    TorusPolynomial::new(ks.map(|k| Torus::new(k << 29)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        let p1 = eighths([4, 2, 6, 0]);
+        let p2 = eighths([4, 4, 4, 2]);
+        assert_eq!(p1 + p2, eighths([0, 6, 2, 2]));
+    }
+
+    #[test]
+    fn test_sub_neg() {
+        let p1 = eighths([4, 2, 6, 1]);
+        let p2 = eighths([2, 4, 4, 2]);
+        assert_eq!(p1 - p2, p1 + (-p2));
+        assert!((p1 - p1).is_zero());
+    }
+
+    #[test]
+    fn test_mod_switch_round() {
+        let noise = TorusPolynomial::new([5, u32::MAX - 7, 1 << 20, 0].map(Torus::new));
+        let p = eighths([1, 7, 0, 4]) + noise;
+        assert_eq!(p.round_to_bits(3), eighths([1, 7, 0, 4]));
+        assert_eq!(p.mod_switch(3), [1, 7, 0, 4]);
+        // just below 1 rounds to 0
+        assert_eq!(noise.mod_switch(3), [0; 4]);
+    }
+
+    #[test]
+    fn test_mul_by_monomial() {
+        let p = TorusPolynomial::new([1, 2, 3, 4].map(Torus::new));
+
+        let expected = [-3, -4, 1, 2].map(|c: i32| Torus::new(c as u32));
+        assert_eq!(p.mul_by_monomial(2).coefs, expected);
+
+        // X^N = -1
+        assert_eq!(p.mul_by_monomial(4), -p);
+        // X^2N = 1
+        assert_eq!(p.mul_by_monomial(8), p);
+        assert_eq!(p.mul_by_monomial(3).mul_by_monomial(5), p);
+    }
+
+    #[test]
+    fn test_int_mul_by_monomial() {
+        let p = IntPolynomial::new([1, 2, 3, 4]);
+        assert_eq!(p.mul_by_monomial(1).coefs, [-4, 1, 2, 3]);
+        assert_eq!(p.mul_by_monomial(6).coefs, [3, 4, -1, -2]);
+    }
+
+    #[test]
+    fn test_mul_monomial_consistency() {
+        let p = TorusPolynomial::new([5, 7, 11, 13, 17, 19, 23, 29].map(Torus::new));
+        for k in 0..16 {
+            let mut m = IntPolynomial::<8>::ZERO;
+            if k < 8 {
+                m[k] = 1;
+            } else {
+                m[k - 8] = -1;
+            }
+            assert_eq!(p * m, p.mul_by_monomial(k));
+        }
+    }
+
+    #[test]
+    fn test_mul_negacyclic() {
+        // (1/8 + 1/4 X) * (2 + X) = 1/4 + (1/8 + 1/2) X + 1/4 X^2
+        // in X^2 + 1: 1/4 - 1/4 + 5/8 X = 5/8 X
+        let p = eighths([1, 2]);
+        let q = IntPolynomial::new([2, 1]);
+        assert_eq!(p * q, eighths([0, 5]));
+    }
+
+    #[test]
+    fn test_mul_distributive() {
+        let p1 = TorusPolynomial::new([3, u32::MAX, 1 << 31, 12345].map(Torus::new));
+        let p2 = TorusPolynomial::new([9, 8, 7, 6].map(Torus::new));
+        let q = IntPolynomial::new([-1, 4, 0, 1024]);
+        assert_eq!((p1 + p2) * q, p1 * q + p2 * q);
+        assert_eq!(p1 * (-q), -(p1 * q));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let p = TorusPolynomial::new(std::array::from_fn::<_, 64, _>(|i| {
+            Torus::new((i as u32).wrapping_mul(0x9e37_79b9))
+        }));
+        let bytes = bincode::serialize(&p).unwrap();
+        assert_eq!(bytes.len(), 64 * 4);
+        assert_eq!(
+            bincode::deserialize::<TorusPolynomial<64>>(&bytes).unwrap(),
+            p
+        );
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TorusPolynomial<64>>(&json).unwrap(),
+            p
+        );
+
+        let q = IntPolynomial::new([1, -2, 3, i32::MIN]);
+        let json = serde_json::to_string(&q).unwrap();
+        assert_eq!(json, r#"{"coefs":[1,-2,3,-2147483648]}"#);
+        assert_eq!(serde_json::from_str::<IntPolynomial<4>>(&json).unwrap(), q);
+
+        // wrong number of coefficients
+        assert!(serde_json::from_str::<IntPolynomial<4>>(r#"{"coefs":[1,2,3]}"#).is_err());
+        assert!(serde_json::from_str::<IntPolynomial<4>>(r#"{"coefs":[1,2,3,4,5]}"#).is_err());
+    }
+}
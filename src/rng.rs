@@ -0,0 +1,166 @@
+//! Seedable CSPRNG for every sampling API of the crate.
+//!
+//! [`TorusRng`] is a ChaCha20 stream: the same seed always gives the same
+//! keys and ciphertexts, so a failing run can be replayed from its seed.
+//! [`TorusRng::fork`] derives independent streams from labels, so that for
+//! example the key, the masks and the noise never share randomness and
+//! drawing more of one does not shift the others.
+
+use crate::lwe::Seed;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+/// ChaCha20 stream of the seed used to derive forks, output uses stream 0
+const FORK_STREAM: u64 = u64::MAX;
+
+#[derive(Clone)]
+pub struct TorusRng {
+    seed: Seed,
+    inner: ChaCha20Rng,
+}
+
+/// first 32 bytes of the ChaCha20 stream of key, as a PRF of stream
+fn prf(key: Seed, stream: u64) -> Seed {
+    let mut rng = ChaCha20Rng::from_seed(key);
+    rng.set_stream(stream);
+    let mut out = [0; 32];
+    rng.fill_bytes(&mut out);
+    out
+}
+
+impl TorusRng {
+    pub fn from_seed(seed: Seed) -> Self {
+        Self {
+            seed,
+            inner: ChaCha20Rng::from_seed(seed),
+        }
+    }
+
+    /// Seeded from the operating system
+    pub fn from_entropy() -> Self {
+        let mut seed = [0; 32];
+        rand::rngs::OsRng.fill_bytes(&mut seed);
+        Self::from_seed(seed)
+    }
+
+    /// Seed to replay this generator from the start
+    pub fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    /// Independent generator for domain_label, which only depends on the
+    /// seed and the label, not on how much of self was already drawn.
+    ///
+    /// The child seed is a cascade of the PRF over the label length and then
+    /// the label in 8-byte blocks, keyed by a key derived from the seed.
+    pub fn fork(&self, domain_label: &str) -> Self {
+        let label = domain_label.as_bytes();
+        let mut key = prf(prf(self.seed, FORK_STREAM), label.len() as u64);
+        for chunk in label.chunks(8) {
+            let mut block = [0; 8];
+            block[..chunk.len()].copy_from_slice(chunk);
+            key = prf(key, u64::from_le_bytes(block));
+        }
+        Self::from_seed(key)
+    }
+}
+
+// the seed is secret
+impl std::fmt::Debug for TorusRng {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TorusRng").finish_non_exhaustive()
+    }
+}
+
+impl RngCore for TorusRng {
+    fn next_u32(&mut self) -> u32 {
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.inner.try_fill_bytes(dest)
+    }
+}
+
+impl CryptoRng for TorusRng {}
+
+impl SeedableRng for TorusRng {
+    type Seed = Seed;
+
+    fn from_seed(seed: Seed) -> Self {
+        TorusRng::from_seed(seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn draw(rng: &mut TorusRng) -> [u64; 4] {
+        std::array::from_fn(|_| rng.gen())
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = TorusRng::from_seed([1; 32]);
+        let mut b = TorusRng::from_seed([1; 32]);
+        assert_eq!(draw(&mut a), draw(&mut b));
+        assert_ne!(draw(&mut a), draw(&mut TorusRng::from_seed([2; 32])));
+
+        // same stream as the underlying ChaCha20
+        let mut chacha = ChaCha20Rng::from_seed([1; 32]);
+        let mut c = TorusRng::from_seed([1; 32]);
+        assert_eq!(c.next_u64(), chacha.next_u64());
+
+        let mut replay = TorusRng::from_seed(a.seed());
+        assert_eq!(draw(&mut replay), draw(&mut TorusRng::from_seed([1; 32])));
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut root = TorusRng::from_seed([3; 32]);
+        let mut key = root.fork("key");
+        // forks do not depend on the state of the parent
+        draw(&mut root);
+        assert_eq!(draw(&mut key), draw(&mut root.fork("key")));
+
+        let labels = [
+            "",
+            "key",
+            "mask",
+            "noise",
+            "noise\0",
+            "12345678",
+            "123456789",
+        ];
+        let mut streams: Vec<[u64; 4]> = labels.iter().map(|l| draw(&mut root.fork(l))).collect();
+        streams.push(draw(&mut TorusRng::from_seed([3; 32])));
+        streams.push(draw(&mut root.fork("key").fork("key")));
+        streams.sort();
+        streams.dedup();
+        assert_eq!(streams.len(), labels.len() + 2);
+
+        // the parent seed matters
+        assert_ne!(
+            draw(&mut root.fork("key")),
+            draw(&mut TorusRng::from_seed([4; 32]).fork("key"))
+        );
+    }
+
+    #[test]
+    fn test_debug_hides_seed() {
+        assert_eq!(
+            format!("{:?}", TorusRng::from_seed([5; 32])),
+            "TorusRng { .. }"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Slice kernels over Torus, for the inner loops of LWE operations.
+//!
+//! Every kernel checks for AVX2 at runtime on x86_64 and falls back to a
+//! portable loop over the raw representation otherwise. Both paths compute
+//! exactly the same wrapping arithmetic.
+
+use crate::{Torus, TorusRepr};
+
+fn repr(ts: &[Torus]) -> &[TorusRepr] {
+    // SAFETY: Torus is repr(transparent) over TorusRepr
+    unsafe { std::slice::from_raw_parts(ts.as_ptr() as *const TorusRepr, ts.len()) }
+}
+
+fn repr_mut(ts: &mut [Torus]) -> &mut [TorusRepr] {
+    // SAFETY: Torus is repr(transparent) over TorusRepr
+    unsafe { std::slice::from_raw_parts_mut(ts.as_mut_ptr() as *mut TorusRepr, ts.len()) }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn has_avx2() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+/// dst[i] += src[i]
+pub fn add_assign_slice(dst: &mut [Torus], src: &[Torus]) {
+    assert_eq!(dst.len(), src.len(), "length mismatch");
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: AVX2 is available
+        return unsafe { avx2::add_assign(repr_mut(dst), repr(src)) };
+    }
+    portable::add_assign(repr_mut(dst), repr(src))
+}
+
+/// dst[i] -= src[i]
+pub fn sub_assign_slice(dst: &mut [Torus], src: &[Torus]) {
+    assert_eq!(dst.len(), src.len(), "length mismatch");
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: AVX2 is available
+        return unsafe { avx2::sub_assign(repr_mut(dst), repr(src)) };
+    }
+    portable::sub_assign(repr_mut(dst), repr(src))
+}
+
+/// dst[i] += src[i] * k
+pub fn scalar_mul_add(dst: &mut [Torus], src: &[Torus], k: i32) {
+    assert_eq!(dst.len(), src.len(), "length mismatch");
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: AVX2 is available
+        return unsafe { avx2::scalar_mul_add(repr_mut(dst), repr(src), k) };
+    }
+    portable::scalar_mul_add(repr_mut(dst), repr(src), k)
+}
+
+/// sum of a[i] * b[i]
+pub fn dot_product_int(a: &[Torus], b: &[i32]) -> Torus {
+    assert_eq!(a.len(), b.len(), "length mismatch");
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: AVX2 is available
+        return Torus::new(unsafe { avx2::dot_product_int(repr(a), b) });
+    }
+    Torus::new(portable::dot_product_int(repr(a), b))
+}
+
+/// sum of the a[i] with b[i] set
+pub fn dot_product_binary(a: &[Torus], b: &[bool]) -> Torus {
+    assert_eq!(a.len(), b.len(), "length mismatch");
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: AVX2 is available
+        return Torus::new(unsafe { avx2::dot_product_binary(repr(a), b) });
+    }
+    Torus::new(portable::dot_product_binary(repr(a), b))
+}
+
+mod portable {
+    use crate::TorusRepr;
+
+    pub fn add_assign(dst: &mut [TorusRepr], src: &[TorusRepr]) {
+        for (d, &s) in dst.iter_mut().zip(src) {
+            *d = d.wrapping_add(s);
+        }
+    }
+
+    pub fn sub_assign(dst: &mut [TorusRepr], src: &[TorusRepr]) {
+        for (d, &s) in dst.iter_mut().zip(src) {
+            *d = d.wrapping_sub(s);
+        }
+    }
+
+    pub fn scalar_mul_add(dst: &mut [TorusRepr], src: &[TorusRepr], k: i32) {
+        for (d, &s) in dst.iter_mut().zip(src) {
+            *d = d.wrapping_add(s.wrapping_mul(k as TorusRepr));
+        }
+    }
+
+    pub fn dot_product_int(a: &[TorusRepr], b: &[i32]) -> TorusRepr {
+        a.iter().zip(b).fold(0, |acc: TorusRepr, (&x, &y)| {
+            acc.wrapping_add(x.wrapping_mul(y as TorusRepr))
+        })
+    }
+
+    pub fn dot_product_binary(a: &[TorusRepr], b: &[bool]) -> TorusRepr {
+        a.iter().zip(b).fold(0, |acc: TorusRepr, (&x, &y)| {
+            acc.wrapping_add(x & (y as TorusRepr).wrapping_neg())
+        })
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use crate::TorusRepr;
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 8;
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn load(xs: &[TorusRepr]) -> __m256i {
+        _mm256_loadu_si256(xs.as_ptr() as *const __m256i)
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn store(xs: &mut [TorusRepr], v: __m256i) {
+        _mm256_storeu_si256(xs.as_mut_ptr() as *mut __m256i, v)
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn horizontal_sum(v: __m256i) -> TorusRepr {
+        let mut lanes = [0; LANES];
+        store(&mut lanes, v);
+        lanes
+            .iter()
+            .fold(0, |acc: TorusRepr, &x| acc.wrapping_add(x))
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add_assign(dst: &mut [TorusRepr], src: &[TorusRepr]) {
+        let mut dst_chunks = dst.chunks_exact_mut(LANES);
+        let mut src_chunks = src.chunks_exact(LANES);
+        for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+            store(d, _mm256_add_epi32(load(d), load(s)));
+        }
+        super::portable::add_assign(dst_chunks.into_remainder(), src_chunks.remainder());
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub_assign(dst: &mut [TorusRepr], src: &[TorusRepr]) {
+        let mut dst_chunks = dst.chunks_exact_mut(LANES);
+        let mut src_chunks = src.chunks_exact(LANES);
+        for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+            store(d, _mm256_sub_epi32(load(d), load(s)));
+        }
+        super::portable::sub_assign(dst_chunks.into_remainder(), src_chunks.remainder());
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn scalar_mul_add(dst: &mut [TorusRepr], src: &[TorusRepr], k: i32) {
+        let kv = _mm256_set1_epi32(k);
+        let mut dst_chunks = dst.chunks_exact_mut(LANES);
+        let mut src_chunks = src.chunks_exact(LANES);
+        for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+            let prod = _mm256_mullo_epi32(load(s), kv);
+            store(d, _mm256_add_epi32(load(d), prod));
+        }
+        super::portable::scalar_mul_add(dst_chunks.into_remainder(), src_chunks.remainder(), k);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn dot_product_int(a: &[TorusRepr], b: &[i32]) -> TorusRepr {
+        let mut acc = _mm256_setzero_si256();
+        let a_chunks = a.chunks_exact(LANES);
+        let b_chunks = b.chunks_exact(LANES);
+        let tail = super::portable::dot_product_int(a_chunks.remainder(), b_chunks.remainder());
+        for (x, y) in a_chunks.zip(b_chunks) {
+            let y = _mm256_loadu_si256(y.as_ptr() as *const __m256i);
+            acc = _mm256_add_epi32(acc, _mm256_mullo_epi32(load(x), y));
+        }
+        horizontal_sum(acc).wrapping_add(tail)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn dot_product_binary(a: &[TorusRepr], b: &[bool]) -> TorusRepr {
+        let mut acc = _mm256_setzero_si256();
+        let a_chunks = a.chunks_exact(LANES);
+        let b_chunks = b.chunks_exact(LANES);
+        let tail = super::portable::dot_product_binary(a_chunks.remainder(), b_chunks.remainder());
+        for (x, y) in a_chunks.zip(b_chunks) {
+            // 8 bools are 8 bytes holding 0 or 1, widened to 0 or -1 per lane
+            let bits = _mm256_cvtepu8_epi32(_mm_loadl_epi64(y.as_ptr() as *const __m128i));
+            let mask = _mm256_sub_epi32(_mm256_setzero_si256(), bits);
+            acc = _mm256_add_epi32(acc, _mm256_and_si256(load(x), mask));
+        }
+        horizontal_sum(acc).wrapping_add(tail)
+    }
+}
+
+/// Fills dst with independent uniform samples, drawing the raw reprs in bulk
+/// from state: same distribution as Torus::uniform_sample
+#[cfg(feature = "random")]
+pub fn fill_uniform(dst: &mut [Torus], state: &mut impl rand::Rng) {
+    state.fill(repr_mut(dst));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random values, enough to exercise every lane
+    fn values(len: usize, seed: u32) -> Vec<u32> {
+        (0..len as u32)
+            .map(|i| (i ^ seed).wrapping_mul(0x9e37_79b9).rotate_left(i % 32))
+            .collect()
+    }
+
+    fn torus(len: usize, seed: u32) -> Vec<Torus> {
+        values(len, seed).into_iter().map(Torus::new).collect()
+    }
+
+    // lengths around the vector width, to cover the scalar tail
+    const LENGTHS: [usize; 7] = [0, 1, 7, 8, 9, 31, 500];
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_fill_uniform_low_bits() {
+        use rand::SeedableRng;
+
+        const SAMPLES: usize = 1 << 20;
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(6);
+        let mut ts = vec![Torus::new(0); SAMPLES];
+        fill_uniform(&mut ts, &mut rng);
+
+        // Pearson statistic of the low byte over its 256 values
+        let mut counts = [0usize; 256];
+        for t in &ts {
+            counts[(t.inner() & 0xff) as usize] += 1;
+        }
+        let expected = SAMPLES as f64 / 256.;
+        let chi2: f64 = counts
+            .iter()
+            .map(|&c| (c as f64 - expected).powi(2) / expected)
+            .sum();
+        // critical value for 255 degrees of freedom at p = 0.001
+        assert!(chi2 < 330.52, "chi2 = {}", chi2);
+
+        // every bit is set half of the time, within 5 standard deviations
+        for bit in 0..TorusRepr::BITS {
+            let ones = ts.iter().filter(|t| t.inner() >> bit & 1 == 1).count() as f64;
+            let std = (SAMPLES as f64 / 4.).sqrt();
+            assert!((ones - SAMPLES as f64 / 2.).abs() < 5. * std, "bit {}", bit);
+        }
+    }
+
+    #[test]
+    fn test_add_sub_assign() {
+        for len in LENGTHS {
+            let a = torus(len, 1);
+            let b = torus(len, 2);
+
+            let mut sum = a.clone();
+            add_assign_slice(&mut sum, &b);
+            let expected: Vec<Torus> = a.iter().zip(&b).map(|(&x, &y)| x + y).collect();
+            assert_eq!(sum, expected);
+
+            sub_assign_slice(&mut sum, &b);
+            assert_eq!(sum, a);
+        }
+    }
+
+    #[test]
+    fn test_scalar_mul_add() {
+        for len in LENGTHS {
+            for k in [0, 1, -1, 3, -128, i32::MIN] {
+                let a = torus(len, 3);
+                let b = torus(len, 4);
+                let mut out = a.clone();
+                scalar_mul_add(&mut out, &b, k);
+                let expected: Vec<Torus> = a.iter().zip(&b).map(|(&x, &y)| x + y * k).collect();
+                assert_eq!(out, expected, "len = {}, k = {}", len, k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dot_product_int() {
+        for len in LENGTHS {
+            let a = torus(len, 5);
+            let b: Vec<i32> = values(len, 6).into_iter().map(|x| x as i32 >> 20).collect();
+            let expected = a
+                .iter()
+                .zip(&b)
+                .fold(Torus::new(0), |acc, (&x, &y)| acc + x * y);
+            assert_eq!(dot_product_int(&a, &b), expected);
+        }
+    }
+
+    #[test]
+    fn test_dot_product_binary() {
+        for len in LENGTHS {
+            let a = torus(len, 7);
+            let b: Vec<bool> = values(len, 8).into_iter().map(|x| x & 1 == 1).collect();
+            let expected = a
+                .iter()
+                .zip(&b)
+                .filter(|(_, &y)| y)
+                .fold(Torus::new(0), |acc, (&x, _)| acc + x);
+            assert_eq!(dot_product_binary(&a, &b), expected);
+
+            let as_int: Vec<i32> = b.iter().map(|&y| y as i32).collect();
+            assert_eq!(dot_product_int(&a, &as_int), expected);
+        }
+    }
+
+    #[test]
+    fn test_portable_matches() {
+        let a = values(37, 9);
+        let b = values(37, 10);
+        let ints: Vec<i32> = b.iter().map(|&x| x as i32).collect();
+        let bools: Vec<bool> = b.iter().map(|&x| x >> 31 == 1).collect();
+
+        let expected = dot_product_int(&torus(37, 9), &ints);
+        assert_eq!(portable::dot_product_int(&a, &ints), expected.inner());
+        let expected = dot_product_binary(&torus(37, 9), &bools);
+        assert_eq!(portable::dot_product_binary(&a, &bools), expected.inner());
+
+        let mut dst = torus(37, 11);
+        let mut raw = values(37, 11);
+        scalar_mul_add(&mut dst, &torus(37, 10), -5);
+        portable::scalar_mul_add(&mut raw, &b, -5);
+        assert_eq!(repr(&dst), &raw[..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "length mismatch")]
+    fn test_length_mismatch() {
+        add_assign_slice(&mut torus(8, 0), &torus(9, 0));
+    }
+}
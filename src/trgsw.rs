@@ -0,0 +1,341 @@
+use crate::decomposition::GadgetDecomposer;
+use crate::polynomial::{IntPolynomial, PolynomialMultiplier, TorusPolynomial};
+use crate::trlwe::TrlweCiphertext;
+#[cfg(feature = "random")]
+use crate::trlwe::TrlweSecretKey;
+use crate::wire::{Kind, WireError, WireFormat, WireReader, WireWriter};
+use num_traits::identities::ConstZero;
+use std::io::{Read, Write};
+
+/// TRGSW sample of a small integer polynomial mu: (k+1) * l TRLWE rows,
+/// row i * l + j encrypts 0 with mu / Bg^(j+1) added to component i
+/// (the i-th mask polynomial, or the body for i = k).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "TrgswCiphertextRaw<N>"))]
+pub struct TrgswCiphertext<const N: usize> {
+    rows: Vec<TrlweCiphertext<N>>,
+    decomposer: GadgetDecomposer,
+}
+
+/// Unchecked fields of a deserialized [`TrgswCiphertext`]
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct TrgswCiphertextRaw<const N: usize> {
+    rows: Vec<TrlweCiphertext<N>>,
+    decomposer: GadgetDecomposer,
+}
+
+#[cfg(feature = "serde")]
+impl<const N: usize> TryFrom<TrgswCiphertextRaw<N>> for TrgswCiphertext<N> {
+    type Error = &'static str;
+
+    fn try_from(raw: TrgswCiphertextRaw<N>) -> Result<Self, Self::Error> {
+        let level = raw.decomposer.level;
+        if raw.rows.is_empty() || !raw.rows.len().is_multiple_of(level) {
+            return Err("row count is not a positive multiple of the level");
+        }
+        let k = raw.rows.len() / level - 1;
+        if raw.rows.iter().any(|row| row.k() != k) {
+            return Err("row dimension mismatch");
+        }
+        Ok(Self {
+            rows: raw.rows,
+            decomposer: raw.decomposer,
+        })
+    }
+}
+
+impl<const N: usize> TrgswCiphertext<N> {
+    /// mu * g added to the rows of zero encryptions
+    fn from_zeros(
+        mut rows: Vec<TrlweCiphertext<N>>,
+        mu: &IntPolynomial<N>,
+        decomposer: GadgetDecomposer,
+    ) -> Self {
+        let k = rows[0].k();
+        let level = decomposer.level;
+        for i in 0..=k {
+            for j in 0..level {
+                let g = TorusPolynomial::new(mu.coefs.map(|c| decomposer.gadget(j) * c));
+                let row = &mut rows[i * level + j];
+                if i < k {
+                    row.mask[i] += g;
+                } else {
+                    row.body += g;
+                }
+            }
+        }
+        Self { rows, decomposer }
+    }
+
+    /// Noiseless encryption of mu under any key with k polynomials
+    pub fn trivial(mu: &IntPolynomial<N>, k: usize, decomposer: GadgetDecomposer) -> Self {
+        let zero = TrlweCiphertext::trivial(TorusPolynomial::ZERO, k);
+        Self::from_zeros(vec![zero; (k + 1) * decomposer.level], mu, decomposer)
+    }
+
+    #[cfg(feature = "random")]
+    pub fn encrypt(
+        key: &TrlweSecretKey<N>,
+        mu: &IntPolynomial<N>,
+        decomposer: GadgetDecomposer,
+        noise_std: f64,
+        mul: &impl PolynomialMultiplier<N>,
+        state: &mut impl rand::Rng,
+    ) -> Self {
+        let rows = (0..(key.k() + 1) * decomposer.level)
+            .map(|_| key.encrypt(&TorusPolynomial::ZERO, noise_std, mul, state))
+            .collect();
+        Self::from_zeros(rows, mu, decomposer)
+    }
+
+    /// [`Self::encrypt`] with the masks and the noise of the rows drawn from
+    /// separate generators, see [`TrlweSecretKey::encrypt_with_streams`]
+    #[cfg(feature = "random")]
+    pub fn encrypt_with_streams(
+        key: &TrlweSecretKey<N>,
+        mu: &IntPolynomial<N>,
+        decomposer: GadgetDecomposer,
+        noise_std: f64,
+        mul: &impl PolynomialMultiplier<N>,
+        mask_state: &mut impl rand::Rng,
+        noise_state: &mut impl rand::Rng,
+    ) -> Self {
+        let rows = (0..(key.k() + 1) * decomposer.level)
+            .map(|_| {
+                key.encrypt_with_streams(
+                    &TorusPolynomial::ZERO,
+                    noise_std,
+                    mul,
+                    mask_state,
+                    noise_state,
+                )
+            })
+            .collect();
+        Self::from_zeros(rows, mu, decomposer)
+    }
+
+    pub fn k(&self) -> usize {
+        self.rows.len() / self.decomposer.level - 1
+    }
+
+    pub fn decomposer(&self) -> GadgetDecomposer {
+        self.decomposer
+    }
+
+    /// self ⊡ ct, an encryption of mu * m
+    pub fn external_product(
+        &self,
+        ct: &TrlweCiphertext<N>,
+        mul: &impl PolynomialMultiplier<N>,
+    ) -> TrlweCiphertext<N> {
+        assert_eq!(self.k(), ct.k(), "dimension mismatch");
+
+        let mut out = TrlweCiphertext::trivial(TorusPolynomial::ZERO, ct.k());
+        let components = ct.mask.iter().chain(std::iter::once(&ct.body));
+        let level = self.decomposer.level;
+        for (i, p) in components.enumerate() {
+            let digits = self.decomposer.decompose_polynomial(p);
+            for (j, digits) in digits.iter().enumerate() {
+                let row = &self.rows[i * level + j];
+                for (o, a) in out.mask.iter_mut().zip(&row.mask) {
+                    *o += mul.mul(digits, a);
+                }
+                out.body += mul.mul(digits, &row.body);
+            }
+        }
+        out
+    }
+
+    /// if_true when self encrypts 1, if_false when it encrypts 0
+    pub fn cmux(
+        &self,
+        if_true: &TrlweCiphertext<N>,
+        if_false: &TrlweCiphertext<N>,
+        mul: &impl PolynomialMultiplier<N>,
+    ) -> TrlweCiphertext<N> {
+        let diff = if_true - if_false;
+        self.external_product(&diff, mul) + if_false.clone()
+    }
+}
+
+impl<const N: usize> TrgswCiphertext<N> {
+    pub(crate) fn write_payload(&self, w: &mut WireWriter<impl Write>) -> Result<(), WireError> {
+        for row in &self.rows {
+            row.write_payload(w)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read_payload(
+        r: &mut WireReader<impl Read>,
+        k: usize,
+        decomposer: GadgetDecomposer,
+    ) -> Result<Self, WireError> {
+        let count = k
+            .checked_add(1)
+            .and_then(|rows| rows.checked_mul(decomposer.level))
+            .ok_or(WireError::InvalidParameters("too many rows"))?;
+        let mut rows = Vec::new();
+        for _ in 0..count {
+            rows.push(TrlweCiphertext::read_payload(r, k)?);
+        }
+        Ok(Self { rows, decomposer })
+    }
+}
+
+impl<const N: usize> WireFormat for TrgswCiphertext<N> {
+    fn write_to(&self, writer: impl Write) -> Result<(), WireError> {
+        let mut w = WireWriter::new(writer, Kind::TrgswCiphertext)?;
+        w.u32(N as u32)?;
+        w.u32(self.k() as u32)?;
+        w.decomposer(self.decomposer)?;
+        self.write_payload(&mut w)
+    }
+
+    fn read_from(reader: impl Read) -> Result<Self, WireError> {
+        let mut r = WireReader::new(reader, Kind::TrgswCiphertext)?;
+        r.degree::<N>()?;
+        let k = r.count()?;
+        let decomposer = r.decomposer()?;
+        Self::read_payload(&mut r, k, decomposer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::FftPlan;
+    use crate::polynomial::eighths;
+    use crate::rng::TorusRng;
+
+    const BASE_LOG: u32 = 6;
+    const LEVEL: usize = 3;
+
+    #[test]
+    fn test_trivial_external_product() {
+        let mul = FftPlan::<8>::new();
+        let mu = IntPolynomial::new([0, 1, 0, 0, 0, 0, 0, 0]);
+        let c = TrgswCiphertext::trivial(&mu, 1, GadgetDecomposer::new(BASE_LOG, LEVEL));
+        let m = eighths([1, 2, 3, 4, 5, 6, 7, 0]);
+        let d = TrlweCiphertext::trivial(m, 1);
+        let key = TrlweSecretKey::from_polys(vec![IntPolynomial::new([1, 0, 1, 1, 0, 0, 1, 0])]);
+        assert_eq!(
+            key.decrypt(&c.external_product(&d, &mul), 3, &mul),
+            m.mul_by_monomial(1)
+        );
+    }
+
+    #[cfg(feature = "random")]
+    mod noise {
+        use super::*;
+
+        const N: usize = 1024;
+        const K: usize = 1;
+        // 2^-25
+        const STD: f64 = 2.98e-8;
+
+        /// Empirical variance of phase - expected over all coefficients
+        fn variance(phase: &TorusPolynomial<N>, expected: &TorusPolynomial<N>) -> f64 {
+            let diff = *phase - *expected;
+            diff.coefs
+                .iter()
+                .map(|&e| e.to_centered_f64().powi(2))
+                .sum::<f64>()
+                / N as f64
+        }
+
+        /// Var(C ⊡ d) <= (k+1) l N (Bg/2)^2 σ_C^2 + (1 + kN) ε^2 + ‖mu‖^2 Var(d)
+        /// with ε = 1 / (2 Bg^l) and binary keys
+        fn external_product_bound(mu_norm2: f64, input_var: f64) -> f64 {
+            let half_base = (1u64 << (BASE_LOG - 1)) as f64;
+            let epsilon = 0.5 / (1u64 << (BASE_LOG * LEVEL as u32)) as f64;
+            ((K + 1) * LEVEL * N) as f64 * half_base.powi(2) * STD.powi(2)
+                + (1 + K * N) as f64 * epsilon.powi(2)
+                + mu_norm2 * input_var
+        }
+
+        #[test]
+        fn test_external_product_noise() {
+            use rand::Rng;
+
+            let mut rng = TorusRng::from_seed([1; 32]);
+            let mul = FftPlan::<N>::new();
+            let decomposer = GadgetDecomposer::new(BASE_LOG, LEVEL);
+            let key = TrlweSecretKey::<N>::generate(K, &mut rng);
+
+            let m = eighths(std::array::from_fn(|_| rng.gen_range(0..8)));
+            let d = key.encrypt(&m, STD, &mul, &mut rng);
+
+            for e in [0, 1, 700] {
+                let mut mu = IntPolynomial::<N>::ZERO;
+                mu.coefs[e] = 1;
+                let c = TrgswCiphertext::encrypt(&key, &mu, decomposer, STD, &mul, &mut rng);
+                let out = c.external_product(&d, &mul);
+
+                let expected = m.mul_by_monomial(e);
+                assert_eq!(key.decrypt(&out, 3, &mul), expected);
+
+                let measured = variance(&key.decrypt_phase(&out, &mul), &expected);
+                let bound = external_product_bound(1., STD.powi(2));
+                assert!(measured <= bound, "{} > {}", measured, bound);
+            }
+        }
+
+        #[test]
+        fn test_cmux() {
+            use rand::Rng;
+
+            let mut rng = TorusRng::from_seed([2; 32]);
+            let mul = FftPlan::<N>::new();
+            let decomposer = GadgetDecomposer::new(BASE_LOG, LEVEL);
+            let key = TrlweSecretKey::<N>::generate(K, &mut rng);
+
+            let m0 = eighths(std::array::from_fn(|_| rng.gen_range(0..8)));
+            let m1 = eighths(std::array::from_fn(|_| rng.gen_range(0..8)));
+            let d0 = key.encrypt(&m0, STD, &mul, &mut rng);
+            let d1 = key.encrypt(&m1, STD, &mul, &mut rng);
+
+            for (bit, expected) in [(0, m0), (1, m1)] {
+                let mut mu = IntPolynomial::<N>::ZERO;
+                mu.coefs[0] = bit;
+                let c = TrgswCiphertext::encrypt(&key, &mu, decomposer, STD, &mul, &mut rng);
+                let out = c.cmux(&d1, &d0, &mul);
+                assert_eq!(key.decrypt(&out, 3, &mul), expected);
+
+                // the difference of two fresh samples has variance 2σ^2
+                let measured = variance(&key.decrypt_phase(&out, &mul), &expected);
+                let bound = external_product_bound(1., 2. * STD.powi(2)) + STD.powi(2);
+                assert!(measured <= bound, "{} > {}", measured, bound);
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_invalid() {
+        use serde_json::{from_value, json, to_value};
+
+        // (k + 1) * level rows of k masks each
+        let c = TrgswCiphertext::<8>::trivial(
+            &IntPolynomial::ZERO,
+            1,
+            GadgetDecomposer::new(BASE_LOG, LEVEL),
+        );
+        let valid = to_value(&c).unwrap();
+        assert_eq!(from_value::<TrgswCiphertext<8>>(valid.clone()).unwrap(), c);
+        let mut bad = valid.clone();
+        bad["rows"].as_array_mut().unwrap().pop();
+        assert!(from_value::<TrgswCiphertext<8>>(bad).is_err());
+        let mut bad = valid.clone();
+        bad["rows"] = json!([]);
+        assert!(from_value::<TrgswCiphertext<8>>(bad).is_err());
+        let mut bad = valid.clone();
+        bad["rows"][0]["mask"].as_array_mut().unwrap().clear();
+        assert!(from_value::<TrgswCiphertext<8>>(bad).is_err());
+        let mut bad = valid;
+        bad["decomposer"] = json!({"base_log": 40, "level": 1});
+        assert!(from_value::<TrgswCiphertext<8>>(bad).is_err());
+    }
+}
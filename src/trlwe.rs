@@ -0,0 +1,641 @@
+#[cfg(feature = "random")]
+use crate::keygen::{BinaryKey, KeyDistribution};
+#[cfg(feature = "random")]
+use crate::lwe::Seed;
+use crate::lwe::{LweCiphertext, LweSecretKey};
+use crate::polynomial::{IntPolynomial, PolynomialMultiplier, TorusPolynomial};
+use crate::wire::{Kind, WireError, WireFormat, WireReader, WireWriter};
+use num_traits::identities::ConstZero;
+use std::io::{Read, Write};
+
+/// Secret key (s_1, ..., s_k), s_i in Z\[X\]/(X^N+1) with coefficients from a
+/// [`KeyDistribution`], binary by default
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrlweSecretKey<const N: usize> {
+    polys: Vec<IntPolynomial<N>>,
+}
+
+/// TRLWE sample (a_1, ..., a_k, b) with b = sum a_i * s_i + m + e
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrlweCiphertext<const N: usize> {
+    pub mask: Vec<TorusPolynomial<N>>,
+    pub body: TorusPolynomial<N>,
+}
+
+/// TRLWE sample whose mask polynomials are expanded from a public seed, so
+/// only the seed and the body are stored
+#[cfg(feature = "random")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SeededTrlweCiphertextRaw<N>"))]
+pub struct SeededTrlweCiphertext<const N: usize> {
+    pub seed: Seed,
+    pub k: usize,
+    pub body: TorusPolynomial<N>,
+}
+
+/// Unchecked fields of a deserialized [`SeededTrlweCiphertext`]
+#[cfg(all(feature = "random", feature = "serde"))]
+#[derive(serde::Deserialize)]
+struct SeededTrlweCiphertextRaw<const N: usize> {
+    seed: Seed,
+    k: usize,
+    body: TorusPolynomial<N>,
+}
+
+#[cfg(all(feature = "random", feature = "serde"))]
+impl<const N: usize> TryFrom<SeededTrlweCiphertextRaw<N>> for SeededTrlweCiphertext<N> {
+    type Error = &'static str;
+
+    // kN mask coefficients, bounded as for SeededLweCiphertext
+    fn try_from(raw: SeededTrlweCiphertextRaw<N>) -> Result<Self, Self::Error> {
+        if raw
+            .k
+            .checked_mul(N)
+            .is_none_or(|len| len > crate::lwe::MAX_SEEDED_DIMENSION)
+        {
+            return Err("too many mask polynomials");
+        }
+        Ok(Self {
+            seed: raw.seed,
+            k: raw.k,
+            body: raw.body,
+        })
+    }
+}
+
+/// TorusRng stream from the seed, fed to TorusPolynomial::uniform_sample,
+/// one polynomial after the other
+#[cfg(feature = "random")]
+fn expand_mask<const N: usize>(seed: Seed, k: usize) -> Vec<TorusPolynomial<N>> {
+    use distr_traits::uniform::UniformSample;
+
+    let mut state = crate::rng::TorusRng::from_seed(seed);
+    (0..k)
+        .map(|_| TorusPolynomial::uniform_sample(&mut state))
+        .collect()
+}
+
+impl<const N: usize> TrlweSecretKey<N> {
+    pub fn from_polys(polys: Vec<IntPolynomial<N>>) -> Self {
+        Self { polys }
+    }
+
+    #[cfg(feature = "random")]
+    pub fn generate(k: usize, state: &mut impl rand::Rng) -> Self {
+        Self::generate_with(&BinaryKey, k, state)
+    }
+
+    /// The k * N coefficients are sampled as one vector, so a
+    /// [`TernaryKey`](crate::keygen::TernaryKey) weight is spread over the
+    /// whole key
+    #[cfg(feature = "random")]
+    pub fn generate_with(
+        distribution: &impl KeyDistribution,
+        k: usize,
+        state: &mut impl rand::Rng,
+    ) -> Self {
+        Self::from_coefs(distribution.sample_coefs(k * N, state))
+    }
+
+    /// Same key for the same seed
+    #[cfg(feature = "random")]
+    pub fn from_seed(distribution: &impl KeyDistribution, k: usize, seed: Seed) -> Self {
+        Self::from_coefs(distribution.sample_coefs_seeded(k * N, seed))
+    }
+
+    #[cfg(feature = "random")]
+    fn from_coefs(coefs: Vec<i32>) -> Self {
+        let polys = coefs
+            .chunks_exact(N)
+            .map(|c| IntPolynomial::new(c.try_into().unwrap()))
+            .collect();
+        Self { polys }
+    }
+
+    /// Same key as [`Self::generate`] for the same state, see
+    /// [`LweSecretKey::generate_ct`]
+    #[cfg(all(feature = "random", feature = "constant_time"))]
+    pub fn generate_ct(k: usize, state: &mut impl rand::Rng) -> Self {
+        let polys = (0..k)
+            .map(|_| IntPolynomial::new(std::array::from_fn(|_| (state.next_u32() >> 31) as i32)))
+            .collect();
+        Self { polys }
+    }
+
+    pub fn k(&self) -> usize {
+        self.polys.len()
+    }
+
+    pub fn polys(&self) -> &[IntPolynomial<N>] {
+        &self.polys
+    }
+
+    /// sum of a_i * s_i
+    fn mask_dot(
+        &self,
+        mask: &[TorusPolynomial<N>],
+        mul: &impl PolynomialMultiplier<N>,
+    ) -> TorusPolynomial<N> {
+        assert_eq!(mask.len(), self.k(), "dimension mismatch");
+        mask.iter()
+            .zip(&self.polys)
+            .fold(TorusPolynomial::ZERO, |acc, (a, s)| acc + mul.mul(s, a))
+    }
+
+    #[cfg(feature = "random")]
+    pub fn encrypt(
+        &self,
+        message: &TorusPolynomial<N>,
+        noise_std: f64,
+        mul: &impl PolynomialMultiplier<N>,
+        state: &mut impl rand::Rng,
+    ) -> TrlweCiphertext<N> {
+        use distr_traits::normal::NormalSample;
+        use distr_traits::uniform::UniformSample;
+
+        let mask: Vec<TorusPolynomial<N>> = (0..self.k())
+            .map(|_| TorusPolynomial::uniform_sample(state))
+            .collect();
+        let noise = TorusPolynomial::normal_sample(0., noise_std, state);
+        self.encrypt_with_mask(mask, message, &noise, mul)
+    }
+
+    /// [`Self::encrypt`] with the mask and the noise drawn from separate
+    /// generators, see [`LweSecretKey::encrypt_with_streams`]
+    #[cfg(feature = "random")]
+    pub fn encrypt_with_streams(
+        &self,
+        message: &TorusPolynomial<N>,
+        noise_std: f64,
+        mul: &impl PolynomialMultiplier<N>,
+        mask_state: &mut impl rand::Rng,
+        noise_state: &mut impl rand::Rng,
+    ) -> TrlweCiphertext<N> {
+        use distr_traits::normal::NormalSample;
+        use distr_traits::uniform::UniformSample;
+
+        let mask: Vec<TorusPolynomial<N>> = (0..self.k())
+            .map(|_| TorusPolynomial::uniform_sample(mask_state))
+            .collect();
+        let noise = TorusPolynomial::normal_sample(0., noise_std, noise_state);
+        self.encrypt_with_mask(mask, message, &noise, mul)
+    }
+
+    /// [`Self::encrypt`] with the noise drawn by the constant-time discrete
+    /// Gaussian sampler, see [`LweSecretKey::encrypt_ct`]
+    #[cfg(all(feature = "random", feature = "constant_time"))]
+    pub fn encrypt_ct(
+        &self,
+        message: &TorusPolynomial<N>,
+        noise_std: f64,
+        mul: &impl PolynomialMultiplier<N>,
+        state: &mut impl rand::Rng,
+    ) -> TrlweCiphertext<N> {
+        use distr_traits::uniform::UniformSample;
+
+        let mask: Vec<TorusPolynomial<N>> = (0..self.k())
+            .map(|_| TorusPolynomial::uniform_sample(state))
+            .collect();
+        let noise = crate::gaussian::encryption_noise_polynomial_ct(noise_std, state);
+        self.encrypt_with_mask(mask, message, &noise, mul)
+    }
+
+    /// Encryption with the mask expanded from a fresh seed drawn from state.
+    /// The noise is still drawn from state, never from the public seed.
+    #[cfg(feature = "random")]
+    pub fn encrypt_seeded(
+        &self,
+        message: &TorusPolynomial<N>,
+        noise_std: f64,
+        mul: &impl PolynomialMultiplier<N>,
+        state: &mut impl rand::Rng,
+    ) -> SeededTrlweCiphertext<N> {
+        use distr_traits::normal::NormalSample;
+
+        let seed: Seed = state.gen();
+        let mask = expand_mask(seed, self.k());
+        let noise = TorusPolynomial::normal_sample(0., noise_std, state);
+        let ct = self.encrypt_with_mask(mask, message, &noise, mul);
+        SeededTrlweCiphertext {
+            seed,
+            k: self.k(),
+            body: ct.body,
+        }
+    }
+
+    #[cfg(feature = "random")]
+    fn encrypt_with_mask(
+        &self,
+        mask: Vec<TorusPolynomial<N>>,
+        message: &TorusPolynomial<N>,
+        noise: &TorusPolynomial<N>,
+        mul: &impl PolynomialMultiplier<N>,
+    ) -> TrlweCiphertext<N> {
+        let body = self.mask_dot(&mask, mul) + *message + *noise;
+
+        TrlweCiphertext { mask, body }
+    }
+
+    /// The LWE key of dimension kN under which sample extraction decrypts
+    pub fn to_lwe_key(&self) -> LweSecretKey {
+        LweSecretKey::from_coefs(self.polys.iter().flat_map(|s| s.coefs).collect())
+    }
+
+    /// b - sum a_i * s_i = m + e
+    pub fn decrypt_phase(
+        &self,
+        ct: &TrlweCiphertext<N>,
+        mul: &impl PolynomialMultiplier<N>,
+    ) -> TorusPolynomial<N> {
+        ct.body - self.mask_dot(&ct.mask, mul)
+    }
+
+    /// Phase with every coefficient rounded to a multiple of 2^-bits
+    pub fn decrypt(
+        &self,
+        ct: &TrlweCiphertext<N>,
+        bits: u32,
+        mul: &impl PolynomialMultiplier<N>,
+    ) -> TorusPolynomial<N> {
+        self.decrypt_phase(ct, mul).round_to_bits(bits)
+    }
+}
+
+#[cfg(feature = "random")]
+impl<const N: usize> SeededTrlweCiphertext<N> {
+    /// The full ciphertext, with the mask expanded from the seed
+    pub fn decompress(&self) -> TrlweCiphertext<N> {
+        TrlweCiphertext {
+            mask: expand_mask(self.seed, self.k),
+            body: self.body,
+        }
+    }
+}
+
+impl<const N: usize> TrlweCiphertext<N> {
+    /// Noiseless encryption of message under any key with k polynomials
+    pub fn trivial(message: TorusPolynomial<N>, k: usize) -> Self {
+        Self {
+            mask: vec![TorusPolynomial::ZERO; k],
+            body: message,
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.mask.len()
+    }
+
+    /// LWE encryption of the coefficient m[index] under [`TrlweSecretKey::to_lwe_key`]
+    pub fn sample_extract(&self, index: usize) -> LweCiphertext {
+        assert!(index < N, "index out of range");
+        let mask = self
+            .mask
+            .iter()
+            .flat_map(|a| {
+                (0..N).map(move |j| {
+                    if j <= index {
+                        a.coefs[index - j]
+                    } else {
+                        -a.coefs[N + index - j]
+                    }
+                })
+            })
+            .collect();
+        LweCiphertext {
+            mask,
+            body: self.body.coefs[index],
+        }
+    }
+
+    /// Encryption of X^e * m
+    pub fn mul_by_monomial(&self, e: usize) -> Self {
+        Self {
+            mask: self.mask.iter().map(|a| a.mul_by_monomial(e)).collect(),
+            body: self.body.mul_by_monomial(e),
+        }
+    }
+}
+
+impl<const N: usize> std::ops::AddAssign<&TrlweCiphertext<N>> for TrlweCiphertext<N> {
+    fn add_assign(&mut self, other: &TrlweCiphertext<N>) {
+        assert_eq!(self.k(), other.k(), "dimension mismatch");
+        for (a, &b) in self.mask.iter_mut().zip(&other.mask) {
+            *a += b;
+        }
+        self.body += other.body;
+    }
+}
+
+impl<const N: usize> std::ops::SubAssign<&TrlweCiphertext<N>> for TrlweCiphertext<N> {
+    fn sub_assign(&mut self, other: &TrlweCiphertext<N>) {
+        assert_eq!(self.k(), other.k(), "dimension mismatch");
+        for (a, &b) in self.mask.iter_mut().zip(&other.mask) {
+            *a -= b;
+        }
+        self.body -= other.body;
+    }
+}
+
+impl<const N: usize> std::ops::Add for TrlweCiphertext<N> {
+    type Output = TrlweCiphertext<N>;
+
+    fn add(mut self, other: TrlweCiphertext<N>) -> TrlweCiphertext<N> {
+        self += &other;
+        self
+    }
+}
+
+impl<const N: usize> std::ops::Add<&TrlweCiphertext<N>> for &TrlweCiphertext<N> {
+    type Output = TrlweCiphertext<N>;
+
+    fn add(self, other: &TrlweCiphertext<N>) -> TrlweCiphertext<N> {
+        let mut out = self.clone();
+        out += other;
+        out
+    }
+}
+
+impl<const N: usize> std::ops::Sub for TrlweCiphertext<N> {
+    type Output = TrlweCiphertext<N>;
+
+    fn sub(mut self, other: TrlweCiphertext<N>) -> TrlweCiphertext<N> {
+        self -= &other;
+        self
+    }
+}
+
+impl<const N: usize> std::ops::Sub<&TrlweCiphertext<N>> for &TrlweCiphertext<N> {
+    type Output = TrlweCiphertext<N>;
+
+    fn sub(self, other: &TrlweCiphertext<N>) -> TrlweCiphertext<N> {
+        let mut out = self.clone();
+        out -= other;
+        out
+    }
+}
+
+impl<const N: usize> std::ops::Neg for TrlweCiphertext<N> {
+    type Output = TrlweCiphertext<N>;
+
+    fn neg(mut self) -> TrlweCiphertext<N> {
+        for a in self.mask.iter_mut() {
+            *a = -*a;
+        }
+        self.body = -self.body;
+        self
+    }
+}
+
+impl<const N: usize> TrlweCiphertext<N> {
+    pub(crate) fn write_payload(&self, w: &mut WireWriter<impl Write>) -> Result<(), WireError> {
+        for p in self.mask.iter().chain([&self.body]) {
+            w.torus_slice(&p.coefs)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read_payload(r: &mut WireReader<impl Read>, k: usize) -> Result<Self, WireError> {
+        let mut mask = Vec::new();
+        for _ in 0..k {
+            mask.push(TorusPolynomial::new(r.torus_array()?));
+        }
+        let body = TorusPolynomial::new(r.torus_array()?);
+        Ok(Self { mask, body })
+    }
+}
+
+impl<const N: usize> WireFormat for TrlweCiphertext<N> {
+    fn write_to(&self, writer: impl Write) -> Result<(), WireError> {
+        let mut w = WireWriter::new(writer, Kind::TrlweCiphertext)?;
+        w.u32(N as u32)?;
+        w.u32(self.k() as u32)?;
+        self.write_payload(&mut w)
+    }
+
+    fn read_from(reader: impl Read) -> Result<Self, WireError> {
+        let mut r = WireReader::new(reader, Kind::TrlweCiphertext)?;
+        r.degree::<N>()?;
+        let k = r.count()?;
+        Self::read_payload(&mut r, k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::FftPlan;
+    use crate::ntt::NttPlan;
+    use crate::polynomial::eighths;
+    use crate::rng::TorusRng;
+    use crate::Torus;
+
+    const STD: f64 = 1e-7;
+
+    #[test]
+    fn test_trivial() {
+        let key = TrlweSecretKey::from_polys(vec![IntPolynomial::new([1, 0, 1, 1])]);
+        let m = eighths([1, 2, 3, 4]);
+        let ct = TrlweCiphertext::trivial(m, 1);
+        assert_eq!(key.decrypt_phase(&ct, &NttPlan::new()), m);
+    }
+
+    #[cfg(feature = "random")]
+    fn round_trip<const N: usize>(k: usize, mul: &impl PolynomialMultiplier<N>) {
+        use rand::Rng;
+
+        let mut rng = TorusRng::from_seed([1; 32]);
+        let key = TrlweSecretKey::<N>::generate(k, &mut rng);
+        let m = eighths(std::array::from_fn(|_| rng.gen_range(0..8)));
+
+        let ct = key.encrypt(&m, STD, mul, &mut rng);
+        assert_eq!(ct.k(), k);
+        assert_eq!(key.decrypt(&ct, 3, mul), m);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_encrypt_decrypt() {
+        round_trip::<1024>(1, &FftPlan::new());
+        round_trip::<256>(2, &FftPlan::new());
+        round_trip::<64>(3, &NttPlan::new());
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_key_distributions() {
+        use crate::keygen::TernaryKey;
+
+        let mut rng = TorusRng::from_seed([2; 32]);
+        let ternary = TernaryKey {
+            hamming_weight: 100,
+        };
+        let key = TrlweSecretKey::<256>::generate_with(&ternary, 2, &mut rng);
+        assert_eq!(key.k(), 2);
+        let weight: usize = key
+            .polys()
+            .iter()
+            .map(|p| p.coefs.iter().filter(|&&c| c != 0).count())
+            .sum();
+        assert_eq!(weight, 100);
+
+        let seed = [4; 32];
+        assert_eq!(
+            TrlweSecretKey::<256>::from_seed(&ternary, 2, seed),
+            TrlweSecretKey::<256>::from_seed(&ternary, 2, seed)
+        );
+
+        let plan = FftPlan::new();
+        let message = TorusPolynomial::new(std::array::from_fn(|i| {
+            Torus::from_bits_fraction(i as i64, 3)
+        }));
+        let ct = key.encrypt(&message, 1e-8, &plan, &mut rng);
+        assert_eq!(key.decrypt(&ct, 3, &plan), message);
+    }
+
+    #[cfg(all(feature = "random", feature = "constant_time"))]
+    #[test]
+    fn test_generate_ct() {
+        use rand::SeedableRng;
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(7);
+        let mut rng_ct = rng.clone();
+        assert_eq!(
+            TrlweSecretKey::<256>::generate(2, &mut rng),
+            TrlweSecretKey::<256>::generate_ct(2, &mut rng_ct)
+        );
+    }
+
+    #[cfg(all(feature = "random", feature = "constant_time"))]
+    #[test]
+    fn test_encrypt_ct_noise() {
+        use crate::gaussian::DiscreteGaussian;
+        use distr_traits::uniform::UniformSample;
+
+        let mut rng = TorusRng::from_seed([4; 32]);
+        let mul = FftPlan::<256>::new();
+        let key = TrlweSecretKey::generate(2, &mut rng);
+        let mut replay = rng.clone();
+        let ct = key.encrypt_ct(&TorusPolynomial::ZERO, 1e-8, &mul, &mut rng);
+
+        let mask: Vec<TorusPolynomial<256>> = (0..2)
+            .map(|_| TorusPolynomial::uniform_sample(&mut replay))
+            .collect();
+        let sampler = DiscreteGaussian::new(1e-8);
+        let noise = TorusPolynomial::new(std::array::from_fn(|_| sampler.sample_ct(&mut replay)));
+        assert_eq!(ct.mask, mask);
+        assert_eq!(key.decrypt_phase(&ct, &mul), noise);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_seeded() {
+        use rand::Rng;
+
+        let mut rng = TorusRng::from_seed([3; 32]);
+        let mul = FftPlan::<256>::new();
+        let key = TrlweSecretKey::generate(2, &mut rng);
+        let m = eighths(std::array::from_fn(|_| rng.gen_range(0..8)));
+
+        let seeded = key.encrypt_seeded(&m, STD, &mul, &mut rng);
+        let ct = seeded.decompress();
+        assert_eq!(ct.k(), 2);
+        assert_eq!(seeded.decompress(), ct);
+        assert_eq!(key.decrypt(&ct, 3, &mul), m);
+
+        // homomorphic operations work on the decompressed ciphertexts
+        let other = key.encrypt_seeded(&m, STD, &mul, &mut rng).decompress();
+        assert_eq!(key.decrypt(&(&ct + &other), 3, &mul), m + m);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_homomorphic_add_sub() {
+        let mut rng = TorusRng::from_seed([4; 32]);
+        let mul = FftPlan::<8>::new();
+        let key = TrlweSecretKey::generate(2, &mut rng);
+
+        let m1 = eighths([1, 2, 3, 4, 5, 6, 7, 0]);
+        let m2 = eighths([7, 7, 7, 7, 1, 1, 1, 1]);
+        let c1 = key.encrypt(&m1, STD, &mul, &mut rng);
+        let c2 = key.encrypt(&m2, STD, &mul, &mut rng);
+
+        assert_eq!(key.decrypt(&(&c1 + &c2), 3, &mul), m1 + m2);
+        assert_eq!(key.decrypt(&(&c1 - &c2), 3, &mul), m1 - m2);
+        assert_eq!(key.decrypt(&(-c1), 3, &mul), -m1);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_sample_extract() {
+        use rand::Rng;
+
+        let mut rng = TorusRng::from_seed([5; 32]);
+        let mul = FftPlan::<16>::new();
+        let key = TrlweSecretKey::generate(2, &mut rng);
+        let lwe_key = key.to_lwe_key();
+        assert_eq!(lwe_key.dimension(), 32);
+
+        let m = eighths(std::array::from_fn(|_| rng.gen_range(0..8)));
+        let ct = key.encrypt(&m, STD, &mul, &mut rng);
+        for index in 0..16 {
+            let extracted = ct.sample_extract(index);
+            assert_eq!(lwe_key.decrypt_phase(&extracted).round_to_bits(3), m[index]);
+        }
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_mul_by_monomial() {
+        let mut rng = TorusRng::from_seed([6; 32]);
+        let mul = FftPlan::<8>::new();
+        let key = TrlweSecretKey::generate(1, &mut rng);
+
+        let m = eighths([1, 2, 3, 4, 5, 6, 7, 0]);
+        let ct = key.encrypt(&m, STD, &mul, &mut rng);
+        for e in 0..16 {
+            let rotated = ct.mul_by_monomial(e);
+            assert_eq!(key.decrypt(&rotated, 3, &mul), m.mul_by_monomial(e));
+        }
+    }
+
+    #[cfg(all(feature = "random", feature = "serde"))]
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut rng = TorusRng::from_seed([7; 32]);
+        let mul = FftPlan::<16>::new();
+        let key = TrlweSecretKey::generate(2, &mut rng);
+        let m = eighths(std::array::from_fn(|i| i as u32 % 8));
+        let ct = key.encrypt(&m, STD, &mul, &mut rng);
+
+        let key2: TrlweSecretKey<16> =
+            bincode::deserialize(&bincode::serialize(&key).unwrap()).unwrap();
+        let ct2: TrlweCiphertext<16> =
+            serde_json::from_str(&serde_json::to_string(&ct).unwrap()).unwrap();
+        assert_eq!(key2, key);
+        assert_eq!(ct2, ct);
+        assert_eq!(key2.decrypt(&ct2, 3, &mul), m);
+    }
+
+    #[cfg(all(feature = "random", feature = "serde"))]
+    #[test]
+    fn test_serde_seeded() {
+        let mut rng = TorusRng::from_seed([8; 32]);
+        let mul = FftPlan::<16>::new();
+        let key = TrlweSecretKey::generate(2, &mut rng);
+        let ct = key.encrypt_seeded(&TorusPolynomial::ZERO, STD, &mul, &mut rng);
+        let mut json = serde_json::to_value(&ct).unwrap();
+        assert_eq!(
+            serde_json::from_value::<SeededTrlweCiphertext<16>>(json.clone()).unwrap(),
+            ct
+        );
+
+        // would allocate every mask polynomial on decompress
+        for k in [u64::MAX, u64::MAX / 16 + 1] {
+            json["k"] = serde_json::json!(k);
+            assert!(serde_json::from_value::<SeededTrlweCiphertext<16>>(json.clone()).is_err());
+        }
+    }
+}
@@ -0,0 +1,525 @@
+//! Versioned little-endian binary format for torus vectors, ciphertexts and
+//! evaluation keys, independent of any serde layout.
+//!
+//! Every object starts with an 8 byte header
+//!
+//! | offset | size | field                                |
+//! |--------|------|--------------------------------------|
+//! | 0      | 4    | magic, `b"FTOR"`                     |
+//! | 4      | 2    | format version, currently 1          |
+//! | 6      | 1    | torus bit width, 32                  |
+//! | 7      | 1    | object kind, see [`Kind`]            |
+//!
+//! followed by the parameters of the object and by its payload:
+//!
+//! | kind                  | parameters                                         | payload                          |
+//! |-----------------------|----------------------------------------------------|----------------------------------|
+//! | 1, torus vector       | len: u64                                           | len values                       |
+//! | 2, LWE ciphertext     | n: u64                                             | n mask values, body              |
+//! | 3, TRLWE ciphertext   | N: u32, k: u32                                     | k mask polynomials, body         |
+//! | 4, TRGSW ciphertext   | N: u32, k: u32, base_log: u32, level: u32          | (k + 1) * level TRLWE rows       |
+//! | 5, bootstrapping key  | N: u32, k: u32, base_log: u32, level: u32, n: u64  | n TRGSW samples                  |
+//! | 6, key switching key  | n_in: u64, n_out: u64, base_log: u32, level: u32   | n_in * level LWE samples         |
+//!
+//! All integers are little-endian and torus values are stored as their raw
+//! 32-bit representation. Nested objects (the rows of a TRGSW sample, the
+//! samples of a key) are stored as their payload only. A bootstrapping key
+//! of dimension 0 is written with k, base_log and level set to 0.
+
+use crate::decomposition::GadgetDecomposer;
+use crate::{Torus, TorusRepr};
+use std::io::{Read, Write};
+
+pub const MAGIC: [u8; 4] = *b"FTOR";
+pub const VERSION: u16 = 1;
+
+/// Number of torus values read or written at once
+const CHUNK: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Kind {
+    TorusVector = 1,
+    LweCiphertext = 2,
+    TrlweCiphertext = 3,
+    TrgswCiphertext = 4,
+    BootstrappingKey = 5,
+    KeySwitchingKey = 6,
+}
+
+#[derive(Debug)]
+pub enum WireError {
+    Io(std::io::Error),
+    /// The input ended before the end of the object
+    Truncated,
+    BadMagic([u8; 4]),
+    UnsupportedVersion(u16),
+    TorusWidthMismatch {
+        expected: u8,
+        found: u8,
+    },
+    KindMismatch {
+        expected: Kind,
+        found: u8,
+    },
+    /// The parameters are out of range, or differ from the requested type
+    InvalidParameters(&'static str),
+}
+
+impl std::fmt::Display for WireError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WireError::Io(e) => write!(f, "i/o error: {}", e),
+            WireError::Truncated => write!(f, "truncated input"),
+            WireError::BadMagic(magic) => write!(f, "bad magic {:?}", magic),
+            WireError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            WireError::TorusWidthMismatch { expected, found } => {
+                write!(f, "expected {}-bit torus, found {}-bit", expected, found)
+            }
+            WireError::KindMismatch { expected, found } => {
+                write!(
+                    f,
+                    "expected {:?} (kind {}), found kind {}",
+                    expected, *expected as u8, found
+                )
+            }
+            WireError::InvalidParameters(what) => write!(f, "invalid parameters: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for WireError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WireError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for WireError {
+    fn from(e: std::io::Error) -> Self {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            WireError::Truncated
+        } else {
+            WireError::Io(e)
+        }
+    }
+}
+
+/// Types with a binary encoding, see the [module documentation](self)
+pub trait WireFormat: Sized {
+    fn write_to(&self, writer: impl Write) -> Result<(), WireError>;
+    fn read_from(reader: impl Read) -> Result<Self, WireError>;
+}
+
+pub(crate) struct WireWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> WireWriter<W> {
+    /// Writes the header of an object of the kind
+    pub fn new(inner: W, kind: Kind) -> Result<Self, WireError> {
+        let mut w = Self { inner };
+        w.inner.write_all(&MAGIC)?;
+        w.u16(VERSION)?;
+        w.inner.write_all(&[TorusRepr::BITS as u8, kind as u8])?;
+        Ok(w)
+    }
+
+    pub fn u16(&mut self, x: u16) -> Result<(), WireError> {
+        Ok(self.inner.write_all(&x.to_le_bytes())?)
+    }
+
+    pub fn u32(&mut self, x: u32) -> Result<(), WireError> {
+        Ok(self.inner.write_all(&x.to_le_bytes())?)
+    }
+
+    pub fn u64(&mut self, x: u64) -> Result<(), WireError> {
+        Ok(self.inner.write_all(&x.to_le_bytes())?)
+    }
+
+    pub fn decomposer(&mut self, decomposer: GadgetDecomposer) -> Result<(), WireError> {
+        self.u32(decomposer.base_log)?;
+        self.u32(decomposer.level as u32)
+    }
+
+    pub fn torus_slice(&mut self, ts: &[Torus]) -> Result<(), WireError> {
+        let mut buf = Vec::with_capacity(CHUNK.min(ts.len()) * std::mem::size_of::<TorusRepr>());
+        for chunk in ts.chunks(CHUNK) {
+            buf.clear();
+            for t in chunk {
+                buf.extend_from_slice(&t.inner().to_le_bytes());
+            }
+            self.inner.write_all(&buf)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct WireReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> WireReader<R> {
+    /// Reads and checks the header of an object of the kind
+    pub fn new(inner: R, kind: Kind) -> Result<Self, WireError> {
+        let mut r = Self { inner };
+        let mut magic = [0; 4];
+        r.inner.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(WireError::BadMagic(magic));
+        }
+        let version = r.u16()?;
+        if version != VERSION {
+            return Err(WireError::UnsupportedVersion(version));
+        }
+        let mut width_kind = [0; 2];
+        r.inner.read_exact(&mut width_kind)?;
+        let [width, found] = width_kind;
+        if width as u32 != TorusRepr::BITS {
+            return Err(WireError::TorusWidthMismatch {
+                expected: TorusRepr::BITS as u8,
+                found: width,
+            });
+        }
+        if found != kind as u8 {
+            return Err(WireError::KindMismatch {
+                expected: kind,
+                found,
+            });
+        }
+        Ok(r)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, WireError> {
+        let mut buf = [0; 2];
+        self.inner.read_exact(&mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, WireError> {
+        let mut buf = [0; 4];
+        self.inner.read_exact(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, WireError> {
+        let mut buf = [0; 8];
+        self.inner.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// A u32 count, as usize
+    pub fn count(&mut self) -> Result<usize, WireError> {
+        usize::try_from(self.u32()?).map_err(|_| WireError::InvalidParameters("count overflows"))
+    }
+
+    /// A u64 length, as usize
+    pub fn len(&mut self) -> Result<usize, WireError> {
+        usize::try_from(self.u64()?).map_err(|_| WireError::InvalidParameters("length overflows"))
+    }
+
+    /// Polynomial size, which must match the requested N
+    pub fn degree<const N: usize>(&mut self) -> Result<(), WireError> {
+        if self.count()? != N {
+            return Err(WireError::InvalidParameters("polynomial size mismatch"));
+        }
+        Ok(())
+    }
+
+    pub fn decomposer(&mut self) -> Result<GadgetDecomposer, WireError> {
+        let base_log = self.u32()?;
+        let level = self.u32()?;
+        checked_decomposer(base_log, level)
+    }
+
+    /// len torus values. The length is not trusted: the output grows with
+    /// the input actually read, so a corrupted length fails as truncated.
+    pub fn torus_vec(&mut self, len: usize) -> Result<Vec<Torus>, WireError> {
+        let size = std::mem::size_of::<TorusRepr>();
+        let mut out = Vec::with_capacity(len.min(CHUNK));
+        let mut buf = vec![0; CHUNK * size];
+        while out.len() < len {
+            let n = (len - out.len()).min(CHUNK);
+            self.inner.read_exact(&mut buf[..n * size])?;
+            out.extend(
+                buf[..n * size]
+                    .chunks_exact(size)
+                    .map(|bytes| Torus::new(TorusRepr::from_le_bytes(bytes.try_into().unwrap()))),
+            );
+        }
+        Ok(out)
+    }
+
+    pub fn torus_array<const N: usize>(&mut self) -> Result<[Torus; N], WireError> {
+        let v = self.torus_vec(N)?;
+        Ok(v.try_into().unwrap())
+    }
+}
+
+/// Decomposition parameters, validated instead of asserted
+pub(crate) fn checked_decomposer(base_log: u32, level: u32) -> Result<GadgetDecomposer, WireError> {
+    let level = level as usize;
+    GadgetDecomposer::check(base_log, level)
+        .map_err(|_| WireError::InvalidParameters("decomposition"))?;
+    Ok(GadgetDecomposer::new(base_log, level))
+}
+
+impl WireFormat for Vec<Torus> {
+    fn write_to(&self, writer: impl Write) -> Result<(), WireError> {
+        let mut w = WireWriter::new(writer, Kind::TorusVector)?;
+        w.u64(self.len() as u64)?;
+        w.torus_slice(self)
+    }
+
+    fn read_from(reader: impl Read) -> Result<Self, WireError> {
+        let mut r = WireReader::new(reader, Kind::TorusVector)?;
+        let len = r.len()?;
+        r.torus_vec(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bootstrap::BootstrappingKey;
+    use crate::keyswitch::KeySwitchingKey;
+    use crate::lwe::LweCiphertext;
+    use crate::polynomial::{IntPolynomial, TorusPolynomial};
+    use crate::rng::TorusRng;
+    use crate::trgsw::TrgswCiphertext;
+    use crate::trlwe::TrlweCiphertext;
+
+    /// Deterministic xorshift, to corrupt inputs reproducibly
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    fn encode<T: WireFormat>(x: &T) -> Vec<u8> {
+        let mut out = Vec::new();
+        x.write_to(&mut out).unwrap();
+        out
+    }
+
+    /// Every strict prefix fails as truncated, and random byte corruptions
+    /// never panic
+    fn check_corruptions<T: WireFormat + PartialEq + std::fmt::Debug>(x: &T) {
+        let bytes = encode(x);
+        assert_eq!(&T::read_from(&bytes[..]).unwrap(), x);
+
+        for len in 0..bytes.len() {
+            assert!(
+                matches!(T::read_from(&bytes[..len]), Err(WireError::Truncated)),
+                "prefix of length {}",
+                len
+            );
+        }
+
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..1000 {
+            let mut corrupted = bytes.clone();
+            for _ in 0..1 + xorshift(&mut state) % 4 {
+                let i = xorshift(&mut state) as usize % corrupted.len();
+                corrupted[i] ^= 1 << (xorshift(&mut state) % 8);
+            }
+            let _ = T::read_from(&corrupted[..]);
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let v = vec![Torus::new(0x0403_0201), Torus::new(0xdead_beef)];
+        let bytes = encode(&v);
+        assert_eq!(
+            bytes,
+            [
+                b"FTOR".as_slice(),
+                &[1, 0, 32, 1],
+                &2u64.to_le_bytes(),
+                &[1, 2, 3, 4, 0xef, 0xbe, 0xad, 0xde],
+            ]
+            .concat()
+        );
+        assert_eq!(Vec::<Torus>::read_from(&bytes[..]).unwrap(), v);
+    }
+
+    #[test]
+    fn test_long_vector() {
+        let v: Vec<Torus> = (0..3 * CHUNK as u32 + 5)
+            .map(|i| Torus::new(i.wrapping_mul(0x9e37_79b9)))
+            .collect();
+        assert_eq!(Vec::<Torus>::read_from(&encode(&v)[..]).unwrap(), v);
+    }
+
+    #[test]
+    fn test_header_errors() {
+        let bytes = encode(&vec![Torus::new(7)]);
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert!(
+            matches!(Vec::<Torus>::read_from(&bad[..]), Err(WireError::BadMagic(m)) if m == *b"XTOR")
+        );
+
+        let mut bad = bytes.clone();
+        bad[4] = 2;
+        assert!(matches!(
+            Vec::<Torus>::read_from(&bad[..]),
+            Err(WireError::UnsupportedVersion(2))
+        ));
+
+        let mut bad = bytes.clone();
+        bad[6] = 64;
+        assert!(matches!(
+            Vec::<Torus>::read_from(&bad[..]),
+            Err(WireError::TorusWidthMismatch {
+                expected: 32,
+                found: 64
+            })
+        ));
+
+        let mut bad = bytes.clone();
+        bad[7] = Kind::LweCiphertext as u8;
+        assert!(matches!(
+            Vec::<Torus>::read_from(&bad[..]),
+            Err(WireError::KindMismatch {
+                expected: Kind::TorusVector,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_huge_length() {
+        // a corrupted length must not be allocated up front
+        let mut bytes = encode(&vec![Torus::new(7)]);
+        bytes[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(Vec::<Torus>::read_from(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn test_io_error() {
+        struct Failing;
+        impl Write for Failing {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = vec![Torus::new(7)].write_to(Failing).unwrap_err();
+        assert!(matches!(err, WireError::Io(_)));
+        assert_eq!(err.to_string(), "i/o error: disk full");
+    }
+
+    #[test]
+    fn test_corruptions() {
+        check_corruptions(&vec![Torus::new(1), Torus::new(u32::MAX), Torus::new(0)]);
+    }
+
+    fn torus(len: usize, seed: u64) -> Vec<Torus> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| Torus::new(xorshift(&mut state) as u32))
+            .collect()
+    }
+
+    fn polynomial<const N: usize>(seed: u64) -> TorusPolynomial<N> {
+        TorusPolynomial::new(torus(N, seed).try_into().unwrap())
+    }
+
+    #[test]
+    fn test_lwe_ciphertext() {
+        let ct = LweCiphertext {
+            mask: torus(10, 1),
+            body: Torus::new(42),
+        };
+        check_corruptions(&ct);
+        assert_eq!(encode(&ct).len(), 8 + 8 + 11 * 4);
+    }
+
+    #[test]
+    fn test_trlwe_ciphertext() {
+        let ct = TrlweCiphertext {
+            mask: vec![polynomial::<4>(2), polynomial(3)],
+            body: polynomial(4),
+        };
+        check_corruptions(&ct);
+        assert!(matches!(
+            TrlweCiphertext::<8>::read_from(&encode(&ct)[..]),
+            Err(WireError::InvalidParameters(_))
+        ));
+    }
+
+    #[test]
+    fn test_trgsw_ciphertext() {
+        let mu = IntPolynomial::new([1, 0, -1, 0]);
+        let ct = TrgswCiphertext::trivial(&mu, 1, GadgetDecomposer::new(4, 2));
+        check_corruptions(&ct);
+
+        // base_log = 0
+        let mut bytes = encode(&ct);
+        bytes[16..20].fill(0);
+        assert!(matches!(
+            TrgswCiphertext::<4>::read_from(&bytes[..]),
+            Err(WireError::InvalidParameters("decomposition"))
+        ));
+        // base_log * level > 32
+        bytes[16] = 17;
+        assert!(matches!(
+            TrgswCiphertext::<4>::read_from(&bytes[..]),
+            Err(WireError::InvalidParameters("decomposition"))
+        ));
+    }
+
+    #[test]
+    fn test_empty_bootstrapping_key() {
+        let bk = BootstrappingKey::<4>::read_from(
+            &[
+                b"FTOR".as_slice(),
+                &[1, 0, 32, 5],
+                &4u32.to_le_bytes(),
+                &[0; 20],
+            ]
+            .concat()[..],
+        )
+        .unwrap();
+        assert_eq!(bk.dimension(), 0);
+        check_corruptions(&bk);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_keys() {
+        use crate::fft::FftPlan;
+        use crate::lwe::LweSecretKey;
+        use crate::trlwe::TrlweSecretKey;
+
+        let mut rng = TorusRng::from_seed([1; 32]);
+        let lwe_key = LweSecretKey::generate(3, &mut rng);
+        let trlwe_key = TrlweSecretKey::<4>::generate(1, &mut rng);
+        let bk = BootstrappingKey::generate(
+            &lwe_key,
+            &trlwe_key,
+            GadgetDecomposer::new(4, 2),
+            1e-9,
+            &FftPlan::new(),
+            &mut rng,
+        );
+        check_corruptions(&bk);
+
+        let ksk = KeySwitchingKey::generate(
+            &trlwe_key.to_lwe_key(),
+            &lwe_key,
+            GadgetDecomposer::new(2, 2),
+            1e-6,
+            &mut rng,
+        );
+        check_corruptions(&ksk);
+    }
+}